@@ -1,25 +1,87 @@
+use std::collections::HashMap;
 use std::sync::Arc;
 
-use tokio::sync::{RwLock, broadcast, mpsc};
+use tokio::sync::{RwLock, broadcast, mpsc, oneshot};
 
-use crate::map::Tile;
+use crate::resources::{self, REGISTRY, Resource};
+use crate::upgrades::Upgrades;
 
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 pub enum BaseMessage {
-    Collected { resource: Tile, amount: u32 },
+    /// Cargaison déchargée par un robot amarré ; la base répond sur `reply`.
+    Collected {
+        robot: usize,
+        resource: Resource,
+        amount: u32,
+        reply: oneshot::Sender<DepositReceipt>,
+    },
+    /// Un robot s'est amarré à ce poste et commence à décharger.
+    Docked { robot: usize, slot: usize },
+    /// Le robot a quitté son poste.
+    Undocked { robot: usize },
+    /// Un collecteur a vidé le gisement de cette case.
+    Depleted { position: (u16, u16) },
+}
+
+/// Réponse de la base à une livraison.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DepositReceipt {
+    /// Cargaison comptée dans les totaux : le robot peut vider sa soute.
+    Accepted,
+    /// Livraison refusée (robot amarré à aucun poste) : le robot garde sa cargaison.
+    Rejected,
 }
 
 #[derive(Debug, Clone)]
 pub enum BroadcastMessage {
-    BaseStats { energy: u32, crystals: u32 },
+    /// Totaux par type de ressource (indices du registre) et score.
+    BaseStats { totals: Vec<u32>, score: u32 },
+    /// Gisement épuisé, à retirer des connaissances des robots.
+    Depleted { position: (u16, u16) },
+    /// Nouveaux niveaux d'amélioration de la flotte et cristaux dépensés au total.
+    Upgraded { upgrades: Upgrades, spent: u32 },
 }
 
 pub struct BaseStateData {
-    pub total_energy: u32,
-    pub total_crystals: u32,
+    /// Unités livrées par type de ressource, indexées comme `resources::REGISTRY`.
+    pub totals: Vec<u32>,
+    /// Cristaux dépensés en améliorations ; les totaux livrés (et le score) n'en tiennent pas compte.
+    pub spent_crystals: u32,
+    pub upgrades: Upgrades,
+    /// Poste occupé par chaque robot amarré.
+    pub docked: HashMap<usize, usize>,
     pub tx_broadcast: broadcast::Sender<BroadcastMessage>,
 }
 
+impl BaseStateData {
+    /// Dépense les cristaux disponibles en améliorations, tant qu'ils suffisent.
+    fn buy_upgrades(&mut self) {
+        let mut bought = false;
+        while let Some((kind, cost)) = self.upgrades.next_purchase() {
+            let available =
+                self.totals[Resource::CRYSTALS.0 as usize].saturating_sub(self.spent_crystals);
+            if available < cost {
+                break;
+            }
+            self.spent_crystals += cost;
+            self.upgrades.raise(kind);
+            bought = true;
+            tracing::info!(
+                "Amélioration {} niveau {} achetée ({} cristaux)",
+                kind.label(),
+                self.upgrades.level(kind),
+                cost
+            );
+        }
+        if bought {
+            let _ = self.tx_broadcast.send(BroadcastMessage::Upgraded {
+                upgrades: self.upgrades,
+                spent: self.spent_crystals,
+            });
+        }
+    }
+}
+
 pub struct Base {
     state: RwLock<BaseStateData>,
 }
@@ -30,8 +92,10 @@ impl Base {
     pub fn new(tx_broadcast: broadcast::Sender<BroadcastMessage>) -> SharedBase {
         Arc::new(Base {
             state: RwLock::new(BaseStateData {
-                total_energy: 0,
-                total_crystals: 0,
+                totals: vec![0; REGISTRY.len()],
+                spent_crystals: 0,
+                upgrades: Upgrades::default(),
+                docked: HashMap::new(),
                 tx_broadcast,
             }),
         })
@@ -40,21 +104,59 @@ impl Base {
     pub async fn run(self: Arc<Self>, mut rx_events: mpsc::Receiver<BaseMessage>) {
         while let Some(msg) = rx_events.recv().await {
             match msg {
-                BaseMessage::Collected { resource, amount } => {
+                BaseMessage::Collected {
+                    robot,
+                    resource,
+                    amount,
+                    reply,
+                } => {
                     let mut guard = self.state.write().await;
-                    match resource {
-                        Tile::Source(_) => {
-                            guard.total_energy = guard.total_energy.saturating_add(amount)
-                        }
-                        Tile::Cristal(_) => {
-                            guard.total_crystals = guard.total_crystals.saturating_add(amount)
-                        }
-                        _ => {} // ignore les autres tuiles
+                    if !guard.docked.contains_key(&robot) {
+                        tracing::warn!(
+                            "Base: livraison du robot {} hors d'un poste refusée",
+                            robot
+                        );
+                        let _ = reply.send(DepositReceipt::Rejected);
+                        continue;
                     }
+                    // Le robot ne vide sa soute qu'à la réception de l'accusé
+                    if reply.send(DepositReceipt::Accepted).is_err() {
+                        tracing::warn!("Base: robot {} parti avant l'accusé de livraison", robot);
+                        continue;
+                    }
+                    let total = &mut guard.totals[resource.0 as usize];
+                    *total = total.saturating_add(amount);
                     let _ = guard.tx_broadcast.send(BroadcastMessage::BaseStats {
-                        energy: guard.total_energy,
-                        crystals: guard.total_crystals,
+                        totals: guard.totals.clone(),
+                        score: resources::score(&guard.totals),
                     });
+                    if resource == Resource::CRYSTALS {
+                        guard.buy_upgrades();
+                    }
+                }
+                BaseMessage::Docked { robot, slot } => {
+                    let mut guard = self.state.write().await;
+                    if let Some((&other, _)) = guard
+                        .docked
+                        .iter()
+                        .find(|&(&r, &s)| s == slot && r != robot)
+                    {
+                        tracing::warn!("Base: poste {} déjà occupé par le robot {}", slot, other);
+                    }
+                    guard.docked.insert(robot, slot);
+                    tracing::info!("Base: robot {} amarré au poste {}", robot, slot);
+                }
+                BaseMessage::Undocked { robot } => {
+                    let mut guard = self.state.write().await;
+                    if let Some(slot) = guard.docked.remove(&robot) {
+                        tracing::info!("Base: robot {} quitte le poste {}", robot, slot);
+                    }
+                }
+                BaseMessage::Depleted { position } => {
+                    let guard = self.state.read().await;
+                    let _ = guard
+                        .tx_broadcast
+                        .send(BroadcastMessage::Depleted { position });
                 }
             }
         }
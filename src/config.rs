@@ -0,0 +1,235 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use crate::docking;
+use crate::dynamics::ResourceDynamics;
+use crate::events::WorldEvents;
+use crate::failures::Reliability;
+use crate::generator;
+use crate::map::Connectivity;
+use crate::movement::Movement;
+use crate::placement;
+use crate::planning::Planner;
+use crate::recovery::RandomWalk;
+use crate::{Result, SimulationError};
+
+/// Paramètres de la simulation passés en ligne de commande.
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// Largeur du monde, par défaut celle du terminal au démarrage.
+    pub width: Option<u16>,
+    /// Hauteur du monde, par défaut celle du terminal au démarrage (moins la ligne de statut).
+    pub height: Option<u16>,
+    /// Carte écrite à la main à charger au lieu de la génération (`--map`).
+    pub map: Option<PathBuf>,
+    /// Fichier où l'éditeur sauvegarde la carte (`--save`), par défaut celui de `--map`.
+    pub save: Option<PathBuf>,
+    /// Traitement des zones non reliées à la base lors de la génération (`--connectivity carve|drop|keep`).
+    pub connectivity: Connectivity,
+    /// Déplacement des robots en quatre ou huit directions (`--movement cardinal|octile`).
+    pub movement: Movement,
+    /// Trajets des collecteurs calculés seuls ou ensemble (`--planner independent|cooperative`).
+    pub planner: Planner,
+    /// Postes d'amarrage de la base, de 1 à `docking::MAX_DOCKS` (`--docks`).
+    pub docks: usize,
+    /// Ticks passés à décharger à un poste (`--unload-time`).
+    pub unload_ticks: u16,
+    /// Termine la partie quand tous les gisements accessibles sont épuisés (`--stop-when-done`).
+    pub stop_when_done: bool,
+    /// Générateur de terrain et ses paramètres (`--generator nom[:clé=valeur,...]`).
+    pub generator: String,
+    /// Stratégie de placement des gisements (`--resources nom[:clé=valeur,...]`).
+    pub resources: String,
+    /// Plaques de sable, d'eau et d'éboulis (`--terrain none|mixed[:clé=valeur,...]`).
+    pub terrain: String,
+    /// Régénération et apparition de gisements (`--dynamics none|dynamic[:clé=valeur,...]`).
+    pub dynamics: String,
+    /// Effondrements, érosion et zones dangereuses (`--events none|world[:clé=valeur,...]`).
+    pub events: String,
+    /// Pannes des robots (`--failures none|breakdowns[:clé=valeur,...]`).
+    pub failures: String,
+    /// Pas au hasard des robots bloqués (`--recovery walk[:steps=8,seed=...]`).
+    pub recovery: String,
+    /// Collecteurs équipés d'un outil amélioré, nécessaire aux cristaux (`--upgraded-collectors`).
+    pub upgraded_collectors: usize,
+    /// Transporteurs, bâtisseurs et réparateurs ajoutés à l'équipe (`--haulers`, `--builders`, `--repairers`).
+    pub haulers: usize,
+    pub builders: usize,
+    pub repairers: usize,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            width: None,
+            height: None,
+            map: None,
+            save: None,
+            connectivity: Connectivity::default(),
+            movement: Movement::default(),
+            planner: Planner::default(),
+            docks: docking::DEFAULT_DOCKS,
+            unload_ticks: docking::DEFAULT_UNLOAD_TICKS,
+            stop_when_done: false,
+            generator: "perlin".to_string(),
+            resources: "uniform".to_string(),
+            terrain: "none".to_string(),
+            dynamics: "none".to_string(),
+            events: "none".to_string(),
+            failures: "none".to_string(),
+            recovery: "walk".to_string(),
+            upgraded_collectors: 1,
+            haulers: 0,
+            builders: 0,
+            repairers: 0,
+        }
+    }
+}
+
+/// Fichier de sauvegarde quand ni `--save` ni `--map` ne sont donnés.
+const DEFAULT_SAVE_PATH: &str = "map.txt";
+/// Plus petite largeur ou hauteur du monde : celle de la base 3×3.
+pub const MIN_WORLD_SIZE: u16 = 3;
+
+/// Paramètres `clé=valeur` d'une option `nom[:clé=valeur,...]` ; chaque clé lue est consommée.
+pub struct SpecParams(HashMap<String, String>);
+
+impl SpecParams {
+    /// Sépare le nom et les paramètres d'une option.
+    pub fn parse(spec: &str) -> std::result::Result<(&str, Self), String> {
+        let (name, pairs) = spec.split_once(':').unwrap_or((spec, ""));
+        let mut params = HashMap::new();
+        for pair in pairs.split(',').filter(|pair| !pair.is_empty()) {
+            let (key, value) = pair
+                .split_once('=')
+                .ok_or_else(|| format!("paramètre sans valeur: {pair}"))?;
+            params.insert(key.to_string(), value.to_string());
+        }
+        Ok((name, SpecParams(params)))
+    }
+
+    pub fn take<T: FromStr>(&mut self, key: &str, default: T) -> std::result::Result<T, String> {
+        match self.0.remove(key) {
+            Some(value) => value
+                .parse()
+                .map_err(|_| format!("{key}: valeur invalide {value}")),
+            None => Ok(default),
+        }
+    }
+
+    pub fn finish(self, name: &str) -> std::result::Result<(), String> {
+        match self.0.keys().next() {
+            Some(key) => Err(format!("paramètre inconnu pour {name}: {key}")),
+            None => Ok(()),
+        }
+    }
+}
+
+fn parse_value<T: std::str::FromStr>(flag: &str, value: Option<String>) -> Result<T> {
+    let value = value
+        .ok_or_else(|| SimulationError::InvalidArgument(format!("{flag} attend une valeur")))?;
+    value
+        .parse()
+        .map_err(|_| SimulationError::InvalidArgument(format!("{flag}: valeur invalide {value}")))
+}
+
+/// Dimension du monde, au moins la taille de la base.
+fn parse_size(flag: &str, value: Option<String>) -> Result<u16> {
+    let size = parse_value(flag, value)?;
+    if size < MIN_WORLD_SIZE {
+        return Err(SimulationError::InvalidArgument(format!(
+            "{flag}: au moins {MIN_WORLD_SIZE} cases"
+        )));
+    }
+    Ok(size)
+}
+
+impl Config {
+    pub fn from_args() -> Result<Self> {
+        Self::parse(std::env::args().skip(1))
+    }
+
+    pub fn save_path(&self) -> PathBuf {
+        self.save
+            .clone()
+            .or_else(|| self.map.clone())
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_SAVE_PATH))
+    }
+
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self> {
+        let mut config = Config::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--width" => config.width = Some(parse_size(&arg, args.next())?),
+                "--height" => config.height = Some(parse_size(&arg, args.next())?),
+                "--map" => config.map = Some(parse_value(&arg, args.next())?),
+                "--save" => config.save = Some(parse_value(&arg, args.next())?),
+                "--connectivity" => config.connectivity = parse_value(&arg, args.next())?,
+                "--movement" => config.movement = parse_value(&arg, args.next())?,
+                "--planner" => config.planner = parse_value(&arg, args.next())?,
+                "--docks" => {
+                    let docks: usize = parse_value(&arg, args.next())?;
+                    if !(1..=docking::MAX_DOCKS).contains(&docks) {
+                        return Err(SimulationError::InvalidArgument(format!(
+                            "--docks: entre 1 et {} postes",
+                            docking::MAX_DOCKS
+                        )));
+                    }
+                    config.docks = docks;
+                }
+                "--unload-time" => config.unload_ticks = parse_value(&arg, args.next())?,
+                "--stop-when-done" => config.stop_when_done = true,
+                "--upgraded-collectors" => {
+                    config.upgraded_collectors = parse_value(&arg, args.next())?
+                }
+                "--haulers" => config.haulers = parse_value(&arg, args.next())?,
+                "--builders" => config.builders = parse_value(&arg, args.next())?,
+                "--repairers" => config.repairers = parse_value(&arg, args.next())?,
+                "--generator" => {
+                    let spec: String = parse_value(&arg, args.next())?;
+                    generator::from_spec(&spec).map_err(SimulationError::InvalidArgument)?;
+                    config.generator = spec;
+                }
+                "--resources" => {
+                    let spec: String = parse_value(&arg, args.next())?;
+                    placement::from_spec(&spec).map_err(SimulationError::InvalidArgument)?;
+                    config.resources = spec;
+                }
+                "--terrain" => {
+                    let spec: String = parse_value(&arg, args.next())?;
+                    generator::TerrainMix::from_spec(&spec)
+                        .map_err(SimulationError::InvalidArgument)?;
+                    config.terrain = spec;
+                }
+                "--dynamics" => {
+                    let spec: String = parse_value(&arg, args.next())?;
+                    ResourceDynamics::from_spec(&spec).map_err(SimulationError::InvalidArgument)?;
+                    config.dynamics = spec;
+                }
+                "--events" => {
+                    let spec: String = parse_value(&arg, args.next())?;
+                    WorldEvents::from_spec(&spec).map_err(SimulationError::InvalidArgument)?;
+                    config.events = spec;
+                }
+                "--failures" => {
+                    let spec: String = parse_value(&arg, args.next())?;
+                    Reliability::from_spec(&spec).map_err(SimulationError::InvalidArgument)?;
+                    config.failures = spec;
+                }
+                "--recovery" => {
+                    let spec: String = parse_value(&arg, args.next())?;
+                    RandomWalk::from_spec(&spec).map_err(SimulationError::InvalidArgument)?;
+                    config.recovery = spec;
+                }
+                _ => {
+                    return Err(SimulationError::InvalidArgument(format!(
+                        "argument inconnu: {arg}"
+                    )));
+                }
+            }
+        }
+        Ok(config)
+    }
+}
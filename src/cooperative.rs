@@ -0,0 +1,421 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+
+use crate::map::Cell;
+use crate::robot::{self, Robot, RobotPosition};
+
+/// Nœuds développés au plus par une recherche espace-temps avant d'y renoncer : le collecteur
+/// se déplace alors seul, comme avec le planificateur indépendant.
+const MAX_EXPANSIONS: usize = 20_000;
+/// Ticks pendant lesquels un collecteur arrêté (minage, attente) garde sa case, renouvelés à chaque tick.
+const HOLD_TICKS: u64 = 4;
+/// Ticks sans relancer la recherche après un échec : elle coûte cher et échouerait sans doute encore.
+const RETRY_AFTER: u64 = 10;
+
+/// Une case occupée à la fin d'un tick, ou le passage d'une case à une autre pendant un tick.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+enum Claim {
+    Cell((u16, u16), u64),
+    Move((u16, u16), (u16, u16), u64),
+}
+
+/// Table de réservation partagée par les collecteurs : chaque réservation appartient à un robot.
+#[derive(Default)]
+pub struct Reservations {
+    claims: HashMap<Claim, usize>,
+    /// Tick en cours, celui où les itinéraires sont suivis et calculés.
+    tick: u64,
+}
+
+impl Reservations {
+    /// Passe au tick `tick` et oublie les réservations passées.
+    pub fn expire(&mut self, tick: u64) {
+        self.tick = tick;
+        self.claims.retain(|claim, _| match *claim {
+            Claim::Cell(_, t) | Claim::Move(_, _, t) => t >= tick,
+        });
+    }
+
+    fn is_free(&self, claim: Claim, id: usize) -> bool {
+        self.claims.get(&claim).is_none_or(|&owner| owner == id)
+    }
+
+    /// Prend toutes les réservations, ou aucune si l'une appartient déjà à un autre robot.
+    fn claim(&mut self, claims: &[Claim], id: usize) -> bool {
+        if !claims.iter().all(|&claim| self.is_free(claim, id)) {
+            return false;
+        }
+        for &claim in claims {
+            self.claims.insert(claim, id);
+        }
+        true
+    }
+
+    fn release(&mut self, claims: &[Claim], id: usize) {
+        for claim in claims {
+            if self.claims.get(claim) == Some(&id) {
+                self.claims.remove(claim);
+            }
+        }
+    }
+}
+
+/// Itinéraire daté d'un collecteur : pour chaque tick où il agit, la case où il se trouve
+/// après son action (la même pour une attente).
+#[derive(Clone, Debug)]
+pub struct Route {
+    pub target: RobotPosition,
+    steps: VecDeque<(u64, RobotPosition)>,
+    claims: Vec<Claim>,
+}
+
+impl Route {
+    /// Il reste des pas à suivre, attentes comprises.
+    pub fn is_active(&self) -> bool {
+        !self.steps.is_empty()
+    }
+}
+
+/// Un pas d'itinéraire coopératif vers `target`. L'itinéraire est gardé tant que le robot le
+/// suit à l'heure ; sinon il est libéré et recalculé. Retourne `false` si aucun itinéraire
+/// n'a été trouvé, ou si la recherche est suspendue après un échec récent.
+pub fn step(
+    robot: &mut Robot,
+    target: RobotPosition,
+    known: &HashMap<(u16, u16), Cell>,
+    reservations: &mut Reservations,
+) -> bool {
+    let tick = reservations.tick;
+    let on_time = robot.route.as_ref().is_some_and(|route| {
+        route.target == target
+            && route.steps.front().is_some_and(|&(t, next)| {
+                t == tick
+                    && (next == robot.position || robot.movement.is_step(robot.position, next))
+                    && (next == target
+                        || robot::known_cost(
+                            &robot.robot_type,
+                            robot::known_cell(robot, known, next),
+                        )
+                        .is_some())
+            })
+    });
+    if !on_time {
+        if let Some(route) = robot.route.take() {
+            reservations.release(&route.claims, robot.id);
+        }
+        if tick < robot.route_retry {
+            return false;
+        }
+        robot.route = plan(robot, target, known, reservations)
+            .filter(|route| reservations.claim(&route.claims, robot.id));
+        if robot.route.is_none() {
+            tracing::info!(
+                "Collecteur {}: pas d'itinéraire vers {:?}, chemin propre pendant {} ticks",
+                robot.id,
+                target,
+                RETRY_AFTER
+            );
+            robot.route_retry = tick + RETRY_AFTER;
+            return false;
+        }
+    }
+    let Some(route) = &mut robot.route else {
+        return false;
+    };
+    if let Some((_, next)) = route.steps.pop_front() {
+        robot.position = next;
+    }
+    true
+}
+
+/// Fin de tick : un itinéraire que le robot n'a pas suivi (recharge, cible abandonnée…) est
+/// libéré ; un collecteur sans pas à venir garde sa case pour les prochains ticks.
+pub fn maintain(
+    robot: &mut Robot,
+    known: &HashMap<(u16, u16), Cell>,
+    reservations: &mut Reservations,
+) {
+    let tick = reservations.tick;
+    if let Some(route) = &robot.route
+        && route.steps.front().is_some_and(|&(t, _)| t <= tick)
+    {
+        reservations.release(&route.claims, robot.id);
+        robot.route = None;
+    }
+    let moving = robot
+        .route
+        .as_ref()
+        .is_some_and(|route| !route.steps.is_empty());
+    if !moving && !is_shared(robot, known, robot.position) {
+        let here = (robot.position.0, robot.position.1);
+        // Jusqu'au premier tick où un itinéraire déjà réservé passe par là
+        for t in tick..=tick + HOLD_TICKS {
+            if !reservations.claim(&[Claim::Cell(here, t)], robot.id) {
+                break;
+            }
+        }
+    }
+}
+
+/// Les cases de la base ne sont pas réservées : les collecteurs peuvent s'y croiser.
+fn is_shared(robot: &Robot, known: &HashMap<(u16, u16), Cell>, position: RobotPosition) -> bool {
+    robot::known_cell(robot, known, position).is_some_and(|cell| cell.is_base())
+}
+
+/// A* espace-temps sur les connaissances du robot : un nœud est une case et le tick où le robot
+/// y agit. Attendre sur place coûte un tick ; entrer sur une case en coûte un plus l'attente
+/// due au terrain (`robot::move_wait`), pendant laquelle la case doit rester libre.
+fn plan(
+    robot: &Robot,
+    target: RobotPosition,
+    known: &HashMap<(u16, u16), Cell>,
+    reservations: &Reservations,
+) -> Option<Route> {
+    let id = robot.id;
+    let start = (robot.position, reservations.tick);
+    let cost = |p: RobotPosition| match robot::known_cost(
+        &robot.robot_type,
+        robot::known_cell(robot, known, p),
+    ) {
+        Some(cost) => Some(cost),
+        None if p == target => Some(1),
+        None => None,
+    };
+    let free = |p: RobotPosition, t: u64| {
+        is_shared(robot, known, p) || reservations.is_free(Claim::Cell((p.0, p.1), t), id)
+    };
+    let heuristic = |p: RobotPosition| robot.movement.min_steps(p, target) as u64;
+
+    let mut heap = BinaryHeap::new();
+    let mut came_from: HashMap<(RobotPosition, u64), (RobotPosition, u64)> = HashMap::new();
+    heap.push(Reverse((
+        heuristic(start.0),
+        0,
+        start.1,
+        start.0.0,
+        start.0.1,
+    )));
+    let mut expansions = 0;
+    let goal = loop {
+        let Reverse((_, _, t, x, y)) = heap.pop()?;
+        let position = RobotPosition(x, y);
+        if position == target {
+            break (position, t);
+        }
+        expansions += 1;
+        if expansions > MAX_EXPANSIONS {
+            return None;
+        }
+
+        let mut successors = Vec::new();
+        if free(position, t) {
+            successors.push((position, t + 1));
+        }
+        for (next, _) in robot.movement.steps(position, cost) {
+            let Some(entry) = cost(next) else {
+                continue;
+            };
+            let wait = robot::move_wait(robot, entry) as u64;
+            let swap = Claim::Move((next.0, next.1), (x, y), t);
+            if (t..=t + wait).all(|tt| free(next, tt))
+                && (is_shared(robot, known, next) || reservations.is_free(swap, id))
+            {
+                successors.push((next, t + wait + 1));
+            }
+        }
+        for node in successors {
+            if node != start && !came_from.contains_key(&node) {
+                came_from.insert(node, (position, t));
+                // À coût égal, le nœud le plus proche de la cible d'abord
+                let h = heuristic(node.0);
+                heap.push(Reverse((
+                    node.1 - start.1 + h,
+                    h,
+                    node.1,
+                    node.0.0,
+                    node.0.1,
+                )));
+            }
+        }
+    };
+
+    // Remonte l'itinéraire et en déduit les réservations
+    let mut steps = VecDeque::new();
+    let mut claims = Vec::new();
+    let mut node = goal;
+    while node != start {
+        let (from, t) = came_from[&node];
+        let (to, arrival) = node;
+        steps.push_front((t, to));
+        if !is_shared(robot, known, to) {
+            let cells = (t..arrival).map(|tt| Claim::Cell((to.0, to.1), tt));
+            claims.extend(cells);
+            if to != from {
+                claims.push(Claim::Move((from.0, from.1), (to.0, to.1), t));
+            }
+        }
+        node = (from, t);
+    }
+    Some(Route {
+        target,
+        steps,
+        claims,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::Terrain;
+
+    /// Cases de sol connues.
+    fn floor(cells: &[(u16, u16)]) -> HashMap<(u16, u16), Cell> {
+        cells
+            .iter()
+            .map(|&position| (position, Cell::new(Terrain::Floor)))
+            .collect()
+    }
+
+    fn collector(id: usize, position: (u16, u16)) -> Robot {
+        let mut robot = robot::robots_collecteur(RobotPosition(0, 0));
+        robot.id = id;
+        robot.position = RobotPosition(position.0, position.1);
+        robot
+    }
+
+    /// Case occupée à chaque tick de `from` à `to`, d'après l'itinéraire.
+    fn timeline(start: (u16, u16), route: &Route, from: u64, to: u64) -> Vec<(u16, u16)> {
+        let mut position = start;
+        (from..=to)
+            .map(|tick| {
+                if let Some(&(_, next)) = route.steps.iter().find(|&&(t, _)| t == tick) {
+                    position = (next.0, next.1);
+                }
+                position
+            })
+            .collect()
+    }
+
+    #[test]
+    fn plan_avoids_reserved_cells() {
+        // Salle de 5×3 ; la case du milieu est réservée longtemps par un autre robot
+        let known = floor(&[
+            (0, 0),
+            (1, 0),
+            (2, 0),
+            (3, 0),
+            (4, 0),
+            (0, 1),
+            (1, 1),
+            (2, 1),
+            (3, 1),
+            (4, 1),
+            (0, 2),
+            (1, 2),
+            (2, 2),
+            (3, 2),
+            (4, 2),
+        ]);
+        let mut reservations = Reservations::default();
+        reservations.expire(1);
+        let blocked: Vec<Claim> = (1..30).map(|t| Claim::Cell((2, 1), t)).collect();
+        assert!(reservations.claim(&blocked, 99));
+
+        let robot = collector(1, (0, 1));
+        let route = plan(&robot, RobotPosition(4, 1), &known, &reservations).unwrap();
+        assert_eq!(
+            route.steps.back().map(|&(_, p)| p),
+            Some(RobotPosition(4, 1))
+        );
+        assert!(route.steps.iter().all(|&(_, p)| p != RobotPosition(2, 1)));
+        assert!(
+            route
+                .claims
+                .iter()
+                .all(|&claim| reservations.is_free(claim, robot.id))
+        );
+        assert!(reservations.claim(&route.claims, robot.id));
+    }
+
+    #[test]
+    fn plan_avoids_collisions_and_swaps() {
+        // Couloir d'une case avec une niche en (1, 1) ; deux robots le parcourent en sens inverse
+        let known = floor(&[(0, 0), (1, 0), (2, 0), (3, 0), (4, 0), (1, 1)]);
+        let mut reservations = Reservations::default();
+        reservations.expire(1);
+
+        let first = collector(1, (4, 0));
+        let first_route = plan(&first, RobotPosition(0, 0), &known, &reservations).unwrap();
+        assert!(reservations.claim(&first_route.claims, first.id));
+
+        let second = collector(2, (0, 0));
+        let second_route = plan(&second, RobotPosition(4, 0), &known, &reservations).unwrap();
+        assert!(reservations.claim(&second_route.claims, second.id));
+        assert_eq!(
+            second_route.steps.back().map(|&(_, p)| p),
+            Some(RobotPosition(4, 0))
+        );
+        // Le second s'efface dans la niche pour laisser passer le premier
+        assert!(
+            second_route
+                .steps
+                .iter()
+                .any(|&(_, p)| p == RobotPosition(1, 1))
+        );
+
+        let end = second_route.steps.back().unwrap().0;
+        let a = timeline((4, 0), &first_route, 0, end);
+        let b = timeline((0, 0), &second_route, 0, end);
+        for t in 1..a.len() {
+            assert_ne!(a[t], b[t], "même case au tick {t}");
+            let swap = a[t - 1] == b[t] && a[t] == b[t - 1] && a[t] != a[t - 1];
+            assert!(!swap, "échange de cases au tick {t}");
+        }
+    }
+
+    #[test]
+    fn claim_is_all_or_nothing() {
+        let mut reservations = Reservations::default();
+        assert!(reservations.claim(&[Claim::Cell((1, 0), 3)], 1));
+        let wanted = [Claim::Cell((0, 0), 2), Claim::Cell((1, 0), 3)];
+        assert!(!reservations.claim(&wanted, 2));
+        assert!(reservations.is_free(Claim::Cell((0, 0), 2), 3));
+        // Ses propres réservations ne le gênent pas
+        assert!(reservations.claim(&[Claim::Cell((1, 0), 3)], 1));
+    }
+
+    #[test]
+    fn failed_search_is_retried_later() {
+        // La cible est hors des cases connues et pas voisine : aucun itinéraire
+        let known = floor(&[(0, 0), (1, 0)]);
+        let mut reservations = Reservations::default();
+        reservations.expire(5);
+        let mut robot = collector(1, (0, 0));
+        assert!(!step(
+            &mut robot,
+            RobotPosition(3, 0),
+            &known,
+            &mut reservations
+        ));
+        assert_eq!(robot.route_retry, 5 + RETRY_AFTER);
+
+        // Pendant l'attente, même une cible atteignable passe par le chemin propre du robot
+        reservations.expire(6);
+        assert!(!step(
+            &mut robot,
+            RobotPosition(1, 0),
+            &known,
+            &mut reservations
+        ));
+        assert!(robot.route.is_none());
+
+        reservations.expire(5 + RETRY_AFTER);
+        assert!(step(
+            &mut robot,
+            RobotPosition(1, 0),
+            &known,
+            &mut reservations
+        ));
+        assert_eq!(robot.position, RobotPosition(1, 0));
+    }
+}
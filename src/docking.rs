@@ -0,0 +1,450 @@
+use std::collections::{HashMap, VecDeque};
+
+use tokio::sync::mpsc::Sender;
+use tokio::sync::oneshot::{self, error::TryRecvError};
+
+use crate::base::{BaseMessage, DepositReceipt};
+use crate::robot::{Robot, RobotPosition};
+
+/// Cases de la couronne de la base, dans l'ordre où elles deviennent des postes : les quatre
+/// cases au contact du centre, puis les coins.
+const RING: [(i16, i16); 8] = [
+    (0, -1),
+    (1, 0),
+    (0, 1),
+    (-1, 0),
+    (-1, -1),
+    (1, -1),
+    (1, 1),
+    (-1, 1),
+];
+/// Nombre maximal de postes : la couronne de la base 3×3.
+pub const MAX_DOCKS: usize = RING.len();
+/// Postes ouverts par défaut (`--docks`) : les quatre cases au contact du centre.
+pub const DEFAULT_DOCKS: usize = 4;
+/// Ticks de déchargement par défaut (`--unload-time`).
+pub const DEFAULT_UNLOAD_TICKS: u16 = 2;
+/// Livraisons manquées d'affilée (refusées, non transmises ou restées sans réponse) avant que
+/// le robot rende son poste et reprenne place dans la file.
+const MAX_ATTEMPTS: u8 = 3;
+/// Ticks d'attente de l'accusé de la base avant de tenir la livraison pour perdue.
+const CONFIRM_TICKS: u16 = 20;
+/// Distance (en cases, diagonales comprises) du centre de la base à laquelle un robot en file attend son tour.
+const QUEUE_DISTANCE: u16 = 2;
+
+/// Où en est la livraison d'un robot chargé.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DockState {
+    /// Tous les postes sont pris : le robot attend son tour près de la base.
+    Queued,
+    /// Poste attribué, le robot s'y rend.
+    Assigned(RobotPosition),
+    /// Amarré au poste, avec les ticks de déchargement restants.
+    Unloading(RobotPosition, u16),
+    /// Cargaison remise à la base, en attente de son accusé, avec les ticks d'attente restants.
+    Confirming(RobotPosition, u16),
+}
+
+/// Bilan des livraisons à la base.
+#[derive(Clone, Copy, Default, Debug)]
+pub struct DepositStats {
+    /// Livraisons comptées par la base.
+    pub confirmed: u32,
+    /// Livraisons refusées par la base.
+    pub rejected: u32,
+    /// Livraisons non transmises (file de la base pleine) ou restées sans réponse.
+    pub lost: u32,
+}
+
+/// Postes d'amarrage de la base, file d'attente des robots chargés et durée du déchargement.
+pub struct Docking {
+    /// Case de chaque poste et robot auquel il est attribué.
+    slots: Vec<(RobotPosition, Option<usize>)>,
+    /// Robots en attente d'un poste, dans l'ordre d'arrivée.
+    queue: VecDeque<usize>,
+    /// Ticks passés amarré avant que la cargaison soit livrée.
+    unload_ticks: u16,
+    /// Accusé attendu de la base pour chaque robot en `Confirming`.
+    pending: HashMap<usize, oneshot::Receiver<DepositReceipt>>,
+    /// Livraisons manquées d'affilée par robot.
+    failures: HashMap<usize, u8>,
+    /// Bilan des livraisons, pour le résumé de fin de partie.
+    pub stats: DepositStats,
+}
+
+impl Docking {
+    pub fn new(base_center: RobotPosition, docks: usize, unload_ticks: u16) -> Self {
+        let slots = RING
+            .iter()
+            .take(docks.clamp(1, MAX_DOCKS))
+            .map(|&(dx, dy)| {
+                let position = RobotPosition(
+                    base_center.0.saturating_add_signed(dx),
+                    base_center.1.saturating_add_signed(dy),
+                );
+                (position, None)
+            })
+            .collect();
+        Docking {
+            slots,
+            queue: VecDeque::new(),
+            unload_ticks,
+            pending: HashMap::new(),
+            failures: HashMap::new(),
+            stats: DepositStats::default(),
+        }
+    }
+
+    /// Robots en attente d'un poste.
+    pub fn queue_len(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// Postes occupés ou attribués.
+    pub fn busy_slots(&self) -> usize {
+        self.slots
+            .iter()
+            .filter(|(_, robot)| robot.is_some())
+            .count()
+    }
+
+    /// Postes ouverts.
+    pub fn slot_count(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Début de tick : les robots qui ne livrent plus (cargaison perdue dans une panne,
+    /// remorquage, dégagement) rendent leur poste ou leur place dans la file, puis les postes
+    /// libres sont attribués dans l'ordre de la file. Un robot qui attend l'accusé de la base
+    /// garde son poste jusqu'à la réponse.
+    pub fn update(&mut self, robots: &mut [Robot], tx_base: &Sender<BaseMessage>) {
+        for robot in robots.iter_mut() {
+            let delivering = matches!(robot.dock, Some(DockState::Confirming(..)))
+                || (robot.collected_resources > 0
+                    && !robot.broken
+                    && !robot.towed
+                    && robot.recovery.is_none());
+            if robot.dock.is_some() && !delivering {
+                self.release(robot, tx_base);
+            }
+        }
+        while let Some(slot) = self.slots.iter().position(|(_, robot)| robot.is_none())
+            && let Some(id) = self.queue.pop_front()
+        {
+            let Some(robot) = robots.iter_mut().find(|r| r.id == id) else {
+                continue;
+            };
+            self.slots[slot].1 = Some(id);
+            robot.dock = Some(DockState::Assigned(self.slots[slot].0));
+            tracing::info!("Robot {}: poste {} attribué", id, slot);
+        }
+    }
+
+    /// Un robot chargé demande un poste : il en obtient un libre si personne n'attend, sinon
+    /// il prend place dans la file.
+    pub fn request(&mut self, robot: &mut Robot) {
+        let free = self.slots.iter().position(|(_, owner)| owner.is_none());
+        match free {
+            Some(slot) if self.queue.is_empty() => {
+                self.slots[slot].1 = Some(robot.id);
+                robot.dock = Some(DockState::Assigned(self.slots[slot].0));
+            }
+            _ => {
+                self.queue.push_back(robot.id);
+                robot.dock = Some(DockState::Queued);
+                tracing::info!(
+                    "Robot {}: en file d'attente ({} devant lui)",
+                    robot.id,
+                    self.queue.len() - 1
+                );
+            }
+        }
+    }
+
+    /// Le robot arrive à son poste : la base l'enregistre et le déchargement commence.
+    pub fn dock(&mut self, robot: &mut Robot, tx_base: &Sender<BaseMessage>) {
+        let Some(DockState::Assigned(position)) = robot.dock else {
+            return;
+        };
+        let _ = tx_base.try_send(BaseMessage::Docked {
+            robot: robot.id,
+            slot: self.slot_index(position),
+        });
+        robot.dock = Some(DockState::Unloading(position, self.unload_ticks));
+        if self.unload_ticks == 0 {
+            self.unload(robot, tx_base);
+        }
+    }
+
+    /// Un tick de déchargement ; le dernier remet la cargaison à la base, puis le robot attend
+    /// son accusé.
+    pub fn unload(&mut self, robot: &mut Robot, tx_base: &Sender<BaseMessage>) {
+        match robot.dock {
+            Some(DockState::Unloading(position, left)) if left > 1 => {
+                robot.dock = Some(DockState::Unloading(position, left - 1));
+            }
+            Some(DockState::Unloading(position, _)) => self.deposit(robot, position, tx_base),
+            Some(DockState::Confirming(position, left)) => {
+                self.confirm(robot, position, left, tx_base)
+            }
+            _ => {}
+        }
+    }
+
+    /// Remet la cargaison à la base, qui répondra par un accusé.
+    fn deposit(
+        &mut self,
+        robot: &mut Robot,
+        position: RobotPosition,
+        tx_base: &Sender<BaseMessage>,
+    ) {
+        let Some(resource) = robot.carried_resource else {
+            self.release(robot, tx_base);
+            return;
+        };
+        let (reply, receipt) = oneshot::channel();
+        let sent = tx_base.try_send(BaseMessage::Collected {
+            robot: robot.id,
+            resource,
+            amount: robot.collected_resources,
+            reply,
+        });
+        match sent {
+            Ok(()) => {
+                self.pending.insert(robot.id, receipt);
+                robot.dock = Some(DockState::Confirming(position, CONFIRM_TICKS));
+            }
+            Err(err) => {
+                self.stats.lost += 1;
+                tracing::warn!("Robot {}: livraison non transmise ({})", robot.id, err);
+                self.retry(robot, position, tx_base);
+            }
+        }
+    }
+
+    /// Lit l'accusé de la base : la soute n'est vidée qu'une fois la livraison comptée. Sans
+    /// réponse après `CONFIRM_TICKS` ticks, la livraison est perdue et recommencée.
+    fn confirm(
+        &mut self,
+        robot: &mut Robot,
+        position: RobotPosition,
+        left: u16,
+        tx_base: &Sender<BaseMessage>,
+    ) {
+        let Some(receipt) = self.pending.get_mut(&robot.id) else {
+            self.retry(robot, position, tx_base);
+            return;
+        };
+        let mut answer = receipt.try_recv();
+        if answer == Err(TryRecvError::Empty) {
+            if left > 1 {
+                robot.dock = Some(DockState::Confirming(position, left - 1));
+                return;
+            }
+            // La base ne peut plus répondre : un accusé déjà envoyé compte encore, sinon elle
+            // ne comptera pas la livraison
+            receipt.close();
+            answer = receipt.try_recv();
+        }
+        self.pending.remove(&robot.id);
+        match answer {
+            Ok(DepositReceipt::Accepted) => {
+                self.stats.confirmed += 1;
+                let amount = robot.collected_resources;
+                robot.collected_resources = 0;
+                robot.carried_resource = None;
+                tracing::info!(" Déposé {} unités", amount);
+                self.release(robot, tx_base);
+            }
+            Ok(DepositReceipt::Rejected) => {
+                self.stats.rejected += 1;
+                tracing::warn!("Robot {}: livraison refusée par la base", robot.id);
+                self.retry(robot, position, tx_base);
+            }
+            Err(_) => {
+                self.stats.lost += 1;
+                tracing::warn!("Robot {}: livraison restée sans réponse", robot.id);
+                self.retry(robot, position, tx_base);
+            }
+        }
+    }
+
+    /// Livraison manquée : le robot garde sa cargaison et s'amarre de nouveau à son poste, ce
+    /// qui le réenregistre auprès de la base. Après `MAX_ATTEMPTS` échecs d'affilée, il rend
+    /// son poste et redemande sa place dans la file.
+    fn retry(&mut self, robot: &mut Robot, position: RobotPosition, tx_base: &Sender<BaseMessage>) {
+        let failures = self.failures.entry(robot.id).or_insert(0);
+        *failures += 1;
+        if *failures < MAX_ATTEMPTS {
+            robot.dock = Some(DockState::Assigned(position));
+            return;
+        }
+        tracing::warn!(
+            "Robot {}: {} livraisons manquées, retour dans la file",
+            robot.id,
+            MAX_ATTEMPTS
+        );
+        self.release(robot, tx_base);
+    }
+
+    /// Rend le poste ou la place dans la file du robot.
+    fn release(&mut self, robot: &mut Robot, tx_base: &Sender<BaseMessage>) {
+        if matches!(
+            robot.dock,
+            Some(DockState::Unloading(..) | DockState::Confirming(..))
+        ) {
+            let _ = tx_base.try_send(BaseMessage::Undocked { robot: robot.id });
+        }
+        self.pending.remove(&robot.id);
+        self.failures.remove(&robot.id);
+        self.queue.retain(|&id| id != robot.id);
+        for (_, owner) in &mut self.slots {
+            if *owner == Some(robot.id) {
+                *owner = None;
+            }
+        }
+        robot.dock = None;
+    }
+
+    fn slot_index(&self, position: RobotPosition) -> usize {
+        self.slots
+            .iter()
+            .position(|&(slot, _)| slot == position)
+            .unwrap_or(0)
+    }
+}
+
+/// Un robot en file attend son tour à `QUEUE_DISTANCE` cases du centre de la base.
+pub fn is_waiting_area(robot: &Robot) -> bool {
+    let (dx, dy) = (
+        robot.position.0.abs_diff(robot.home.0),
+        robot.position.1.abs_diff(robot.home.1),
+    );
+    dx.max(dy) <= QUEUE_DISTANCE
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::sync::mpsc::{self, Receiver};
+
+    use super::*;
+    use crate::resources::Resource;
+    use crate::robot;
+
+    const CENTER: RobotPosition = RobotPosition(5, 5);
+
+    fn loaded(id: usize) -> Robot {
+        let mut robot = robot::robots_collecteur(CENTER);
+        robot.id = id;
+        robot.collected_resources = 2;
+        robot.carried_resource = Some(Resource::ENERGY);
+        robot
+    }
+
+    /// Amarre le robot à son poste et le décharge jusqu'à la remise de la cargaison.
+    fn deliver(docking: &mut Docking, robot: &mut Robot, tx: &Sender<BaseMessage>) {
+        docking.dock(robot, tx);
+        while matches!(robot.dock, Some(DockState::Unloading(..))) {
+            docking.unload(robot, tx);
+        }
+    }
+
+    /// Accusé de réception de la base à la dernière livraison envoyée.
+    fn last_reply(rx: &mut Receiver<BaseMessage>) -> oneshot::Sender<DepositReceipt> {
+        let mut reply = None;
+        while let Ok(message) = rx.try_recv() {
+            if let BaseMessage::Collected { reply: sender, .. } = message {
+                reply = Some(sender);
+            }
+        }
+        reply.expect("aucune livraison envoyée")
+    }
+
+    #[test]
+    fn request_queues_when_slots_are_busy() {
+        let (tx, _rx) = mpsc::channel(16);
+        let mut docking = Docking::new(CENTER, 1, 1);
+        let mut robots = vec![loaded(1), loaded(2)];
+
+        docking.request(&mut robots[0]);
+        docking.request(&mut robots[1]);
+        assert_eq!(
+            robots[0].dock,
+            Some(DockState::Assigned(RobotPosition(5, 4)))
+        );
+        assert_eq!(robots[1].dock, Some(DockState::Queued));
+        assert_eq!(docking.queue_len(), 1);
+
+        // Le premier perd sa cargaison : son poste passe au suivant de la file
+        robots[0].collected_resources = 0;
+        docking.update(&mut robots, &tx);
+        assert_eq!(robots[0].dock, None);
+        assert_eq!(
+            robots[1].dock,
+            Some(DockState::Assigned(RobotPosition(5, 4)))
+        );
+        assert_eq!(docking.queue_len(), 0);
+        assert_eq!(docking.busy_slots(), 1);
+    }
+
+    #[test]
+    fn accepted_delivery_empties_the_hold_and_frees_the_slot() {
+        let (tx, mut rx) = mpsc::channel(16);
+        let mut docking = Docking::new(CENTER, 1, 2);
+        let mut robot = loaded(1);
+
+        docking.request(&mut robot);
+        deliver(&mut docking, &mut robot, &tx);
+        assert!(matches!(robot.dock, Some(DockState::Confirming(..))));
+        last_reply(&mut rx).send(DepositReceipt::Accepted).unwrap();
+
+        docking.unload(&mut robot, &tx);
+        assert_eq!(robot.collected_resources, 0);
+        assert_eq!(robot.dock, None);
+        assert_eq!(docking.busy_slots(), 0);
+        assert_eq!(docking.stats.confirmed, 1);
+    }
+
+    #[test]
+    fn rejected_delivery_is_retried_then_released() {
+        let (tx, mut rx) = mpsc::channel(16);
+        let mut docking = Docking::new(CENTER, 1, 1);
+        let mut robot = loaded(1);
+
+        docking.request(&mut robot);
+        for attempt in 1..=MAX_ATTEMPTS {
+            deliver(&mut docking, &mut robot, &tx);
+            last_reply(&mut rx).send(DepositReceipt::Rejected).unwrap();
+            docking.unload(&mut robot, &tx);
+            if attempt < MAX_ATTEMPTS {
+                // Il garde son poste et s'y amarre de nouveau
+                assert_eq!(robot.dock, Some(DockState::Assigned(RobotPosition(5, 4))));
+            }
+        }
+        assert_eq!(robot.dock, None);
+        assert_eq!(robot.collected_resources, 2);
+        assert_eq!(docking.busy_slots(), 0);
+        assert_eq!(docking.stats.rejected, u32::from(MAX_ATTEMPTS));
+    }
+
+    #[test]
+    fn unanswered_delivery_is_lost_after_deadline() {
+        let (tx, mut rx) = mpsc::channel(16);
+        let mut docking = Docking::new(CENTER, 1, 1);
+        let mut robot = loaded(1);
+
+        docking.request(&mut robot);
+        deliver(&mut docking, &mut robot, &tx);
+        let reply = last_reply(&mut rx);
+        for _ in 1..CONFIRM_TICKS {
+            docking.unload(&mut robot, &tx);
+            assert!(matches!(robot.dock, Some(DockState::Confirming(..))));
+        }
+        docking.unload(&mut robot, &tx);
+        assert_eq!(robot.dock, Some(DockState::Assigned(RobotPosition(5, 4))));
+        assert_eq!(robot.collected_resources, 2);
+        assert_eq!(docking.stats.lost, 1);
+        // Un accusé tardif n'est plus reçu : la base ne compte pas la livraison abandonnée
+        assert!(reply.send(DepositReceipt::Accepted).is_err());
+    }
+}
@@ -0,0 +1,131 @@
+use std::collections::HashMap;
+
+use rand::{Rng, SeedableRng, rngs::StdRng};
+
+use crate::config::SpecParams;
+use crate::map::{self, Cell, Deposit};
+use crate::resources::Resource;
+
+/// Évolution des gisements pendant la partie : les sources d'énergie se rechargent
+/// et de nouveaux gisements apparaissent sur le sol atteignable.
+pub struct ResourceDynamics {
+    /// Ticks entre deux unités regagnées par chaque source d'énergie (0 : pas de régénération).
+    pub regen_every: u64,
+    /// Quantité au-delà de laquelle une source ne se recharge plus ; une source épuisée
+    /// réapparaît quand elle l'a regagnée.
+    pub regen_max: u32,
+    /// Ticks entre deux apparitions de gisement (0 : aucune).
+    pub spawn_every: u64,
+    pub seed: u64,
+    rng: StdRng,
+    /// Emplacements des sources d'énergie, même épuisées, et unités regagnées
+    /// par celles qui sont épuisées.
+    sources: HashMap<(u16, u16), u32>,
+}
+
+impl ResourceDynamics {
+    /// `none` ou `dynamic[:regen=40,max=10,spawn=300,seed=...]`.
+    pub fn from_spec(spec: &str) -> Result<Option<Self>, String> {
+        let (name, mut params) = SpecParams::parse(spec)?;
+        let dynamics = match name {
+            "none" => None,
+            "dynamic" => {
+                let seed = params.take("seed", rand::random())?;
+                Some(ResourceDynamics {
+                    regen_every: params.take("regen", 40)?,
+                    regen_max: params.take("max", 10)?,
+                    spawn_every: params.take("spawn", 300)?,
+                    seed,
+                    rng: StdRng::seed_from_u64(seed),
+                    sources: HashMap::new(),
+                })
+            }
+            other => {
+                return Err(format!(
+                    "dynamique inconnue: {other} (disponibles: none, dynamic)"
+                ));
+            }
+        };
+        params.finish(name)?;
+        Ok(dynamics)
+    }
+
+    /// Retient les sources d'énergie présentes sur la carte.
+    pub fn track_sources(&mut self, map: &[Vec<Cell>]) {
+        for (y, row) in map.iter().enumerate() {
+            for (x, cell) in row.iter().enumerate() {
+                if cell.deposit_left().map(|d| d.resource) == Some(Resource::ENERGY) {
+                    self.sources.insert((x as u16, y as u16), 0);
+                }
+            }
+        }
+        tracing::info!(
+            "Dynamique des gisements (graine {}): {} sources suivies",
+            self.seed,
+            self.sources.len()
+        );
+    }
+
+    /// L'emplacement ne porte plus de source (édition manuelle).
+    pub fn forget(&mut self, x: u16, y: u16) {
+        self.sources.remove(&(x, y));
+    }
+
+    pub fn step(&mut self, tick: u64, map: &mut [Vec<Cell>], base_center: (u16, u16)) {
+        if self.regen_every > 0 && tick.is_multiple_of(self.regen_every) {
+            self.regenerate(map);
+        }
+        if self.spawn_every > 0 && tick.is_multiple_of(self.spawn_every) {
+            self.spawn(map, base_center);
+        }
+    }
+
+    /// Chaque source gagne une unité. Une source épuisée se recharge sans être visible
+    /// et réapparaît pleine : elle devra être signalée à nouveau par un éclaireur.
+    fn regenerate(&mut self, map: &mut [Vec<Cell>]) {
+        for (&(x, y), regrown) in self.sources.iter_mut() {
+            let cell = &mut map[y as usize][x as usize];
+            if cell.is_free_floor() {
+                *regrown += 1;
+                if *regrown >= self.regen_max {
+                    *regrown = 0;
+                    cell.deposit = Some(Deposit::new(Resource::ENERGY, self.regen_max));
+                    tracing::info!("Source ({}, {}) régénérée", x, y);
+                }
+            } else if let Some(deposit) = &mut cell.deposit
+                && deposit.resource == Resource::ENERGY
+                && deposit.qty < self.regen_max
+            {
+                deposit.qty += 1;
+            }
+        }
+    }
+
+    /// Pose un nouveau gisement sur une case de sol nu atteignable depuis la base ;
+    /// son type est tiré selon la rareté du registre.
+    fn spawn(&mut self, map: &mut [Vec<Cell>], base_center: (u16, u16)) {
+        let mut candidates: Vec<(u16, u16)> = map::reachable_from(map, base_center)
+            .into_iter()
+            .filter(|&(x, y)| map[y as usize][x as usize].is_free_floor())
+            .collect();
+        if candidates.is_empty() {
+            return;
+        }
+        // Ordre du HashSet non déterministe : on trie pour que la graine suffise
+        candidates.sort_unstable();
+        let (x, y) = candidates[self.rng.gen_range(0..candidates.len())];
+        let resource = Resource::random(&mut self.rng);
+        let qty = self.rng.gen_range(5..=10);
+        map[y as usize][x as usize].deposit = Some(Deposit::new(resource, qty));
+        if resource == Resource::ENERGY {
+            self.sources.insert((x, y), 0);
+        }
+        tracing::info!(
+            "Nouveau gisement {} ({}) en ({}, {})",
+            resource.kind().name,
+            qty,
+            x,
+            y
+        );
+    }
+}
@@ -0,0 +1,225 @@
+use std::collections::HashSet;
+
+use rand::{Rng, SeedableRng, rngs::StdRng};
+
+use crate::config::SpecParams;
+use crate::map::{self, Cell, Terrain};
+use crate::robot::RobotType;
+
+/// Zone dangereuse temporaire.
+struct Hazard {
+    cells: Vec<(u16, u16)>,
+    expires_at: u64,
+}
+
+/// Événements qui modifient le monde en cours de partie : couloirs effondrés,
+/// murs érodés et zones dangereuses temporaires.
+pub struct WorldEvents {
+    /// Ticks entre deux effondrements (0 : aucun).
+    pub collapse_every: u64,
+    /// Ticks entre deux érosions (0 : aucune).
+    pub erode_every: u64,
+    /// Ticks entre deux zones dangereuses (0 : aucune).
+    pub hazard_every: u64,
+    pub hazard_radius: u16,
+    pub hazard_duration: u64,
+    pub seed: u64,
+    rng: StdRng,
+    hazards: Vec<Hazard>,
+}
+
+/// Nombre de cases tirées au sort avant de renoncer à un effondrement.
+const COLLAPSE_ATTEMPTS: usize = 20;
+
+impl WorldEvents {
+    /// `none` ou `world[:collapse=250,erode=250,hazard=400,radius=2,duration=150,seed=...]`.
+    pub fn from_spec(spec: &str) -> Result<Option<Self>, String> {
+        let (name, mut params) = SpecParams::parse(spec)?;
+        let events = match name {
+            "none" => None,
+            "world" => {
+                let seed = params.take("seed", rand::random())?;
+                Some(WorldEvents {
+                    collapse_every: params.take("collapse", 250)?,
+                    erode_every: params.take("erode", 250)?,
+                    hazard_every: params.take("hazard", 400)?,
+                    hazard_radius: params.take("radius", 2)?,
+                    hazard_duration: params.take("duration", 150)?,
+                    seed,
+                    rng: StdRng::seed_from_u64(seed),
+                    hazards: Vec::new(),
+                })
+            }
+            other => {
+                return Err(format!(
+                    "événements inconnus: {other} (disponibles: none, world)"
+                ));
+            }
+        };
+        params.finish(name)?;
+        Ok(events)
+    }
+
+    /// Applique les événements du tick et renvoie les cases modifiées.
+    /// `occupied` contient les positions des robots, jamais transformées en mur.
+    pub fn step(
+        &mut self,
+        tick: u64,
+        map: &mut [Vec<Cell>],
+        base_center: (u16, u16),
+        occupied: &HashSet<(u16, u16)>,
+    ) -> Vec<(u16, u16)> {
+        let mut changed = self.expire_hazards(tick, map);
+        if self.collapse_every > 0 && tick.is_multiple_of(self.collapse_every) {
+            changed.extend(self.collapse(map, base_center, occupied));
+        }
+        if self.erode_every > 0 && tick.is_multiple_of(self.erode_every) {
+            changed.extend(self.erode(map));
+        }
+        if self.hazard_every > 0 && tick.is_multiple_of(self.hazard_every) {
+            changed.extend(self.spawn_hazard(tick, map, base_center));
+        }
+        changed
+    }
+
+    /// Tire une case au hasard parmi celles qui vérifient `keep`.
+    fn pick(
+        &mut self,
+        map: &[Vec<Cell>],
+        keep: impl Fn(u16, u16, &Cell) -> bool,
+    ) -> Option<(u16, u16)> {
+        let candidates: Vec<(u16, u16)> = map
+            .iter()
+            .enumerate()
+            .flat_map(|(y, row)| {
+                row.iter()
+                    .enumerate()
+                    .map(move |(x, cell)| (x as u16, y as u16, cell))
+            })
+            .filter(|&(x, y, cell)| keep(x, y, cell))
+            .map(|(x, y, _)| (x, y))
+            .collect();
+        if candidates.is_empty() {
+            return None;
+        }
+        Some(candidates[self.rng.gen_range(0..candidates.len())])
+    }
+
+    /// Un couloir (case de sol nu entre deux cases franchissables) devient un mur,
+    /// à condition de ne couper aucune zone de la base.
+    fn collapse(
+        &mut self,
+        map: &mut [Vec<Cell>],
+        base_center: (u16, u16),
+        occupied: &HashSet<(u16, u16)>,
+    ) -> Option<(u16, u16)> {
+        let height = map.len() as u16;
+        let width = map.first().map_or(0, |row| row.len()) as u16;
+        let reachable = map::reachable_from(map, base_center).len();
+        for _ in 0..COLLAPSE_ATTEMPTS {
+            let (x, y) = self.pick(map, |x, y, cell| {
+                let open_neighbours = map::neighbours(x, y, width, height)
+                    .filter(|&(nx, ny)| {
+                        map[ny as usize][nx as usize].is_passable(&RobotType::Collecteur)
+                    })
+                    .count();
+                cell.is_free_floor()
+                    && open_neighbours == 2
+                    && !occupied.contains(&(x, y))
+                    && (x.abs_diff(base_center.0) > 2 || y.abs_diff(base_center.1) > 2)
+            })?;
+            let previous = map[y as usize][x as usize];
+            map[y as usize][x as usize] = Cell::new(Terrain::Wall);
+            if map::reachable_from(map, base_center).len() + 1 == reachable {
+                tracing::info!("Événement: effondrement en ({}, {})", x, y);
+                return Some((x, y));
+            }
+            map[y as usize][x as usize] = previous;
+        }
+        None
+    }
+
+    /// Un mur qui touche une case franchissable s'érode en sol (jamais sur le bord).
+    fn erode(&mut self, map: &mut [Vec<Cell>]) -> Option<(u16, u16)> {
+        let height = map.len() as u16;
+        let width = map.first().map_or(0, |row| row.len()) as u16;
+        let (x, y) = self.pick(map, |x, y, cell| {
+            cell.terrain == Terrain::Wall
+                && x > 0
+                && y > 0
+                && x + 1 < width
+                && y + 1 < height
+                && map::neighbours(x, y, width, height).any(|(nx, ny)| {
+                    map[ny as usize][nx as usize].is_passable(&RobotType::Collecteur)
+                })
+        })?;
+        map[y as usize][x as usize].terrain = Terrain::Floor;
+        tracing::info!("Événement: érosion en ({}, {})", x, y);
+        Some((x, y))
+    }
+
+    /// Zone dangereuse en losange autour d'une case franchissable hors de la base.
+    fn spawn_hazard(
+        &mut self,
+        tick: u64,
+        map: &mut [Vec<Cell>],
+        base_center: (u16, u16),
+    ) -> Vec<(u16, u16)> {
+        let Some((cx, cy)) = self.pick(map, |_, _, cell| {
+            cell.is_passable(&RobotType::Eclaireur) && !cell.is_base()
+        }) else {
+            return Vec::new();
+        };
+        let radius = self.hazard_radius;
+        let mut cells = Vec::new();
+        for y in cy.saturating_sub(radius)..=cy.saturating_add(radius) {
+            for x in cx.saturating_sub(radius)..=cx.saturating_add(radius) {
+                let inside = x.abs_diff(cx) + y.abs_diff(cy) <= radius;
+                let Some(cell) = map
+                    .get_mut(y as usize)
+                    .and_then(|row| row.get_mut(x as usize))
+                else {
+                    continue;
+                };
+                let near_base = x.abs_diff(base_center.0) <= 1 && y.abs_diff(base_center.1) <= 1;
+                if inside && !near_base && cell.terrain != Terrain::Wall {
+                    cell.hazard = true;
+                    cells.push((x, y));
+                }
+            }
+        }
+        tracing::info!(
+            "Événement: zone dangereuse de {} cases autour de ({}, {})",
+            cells.len(),
+            cx,
+            cy
+        );
+        self.hazards.push(Hazard {
+            cells: cells.clone(),
+            expires_at: tick + self.hazard_duration,
+        });
+        cells
+    }
+
+    /// Retire les zones expirées, sauf les cases encore couvertes par une autre zone.
+    fn expire_hazards(&mut self, tick: u64, map: &mut [Vec<Cell>]) -> Vec<(u16, u16)> {
+        let (expired, active): (Vec<Hazard>, Vec<Hazard>) = self
+            .hazards
+            .drain(..)
+            .partition(|hazard| hazard.expires_at <= tick);
+        self.hazards = active;
+        let still_active: HashSet<(u16, u16)> = self
+            .hazards
+            .iter()
+            .flat_map(|hazard| hazard.cells.iter().copied())
+            .collect();
+        let mut changed = Vec::new();
+        for (x, y) in expired.into_iter().flat_map(|hazard| hazard.cells) {
+            if !still_active.contains(&(x, y)) {
+                map[y as usize][x as usize].hazard = false;
+                changed.push((x, y));
+            }
+        }
+        changed
+    }
+}
@@ -0,0 +1,109 @@
+use std::collections::HashMap;
+
+use rand::{Rng, SeedableRng, rngs::StdRng};
+
+use crate::config::SpecParams;
+use crate::lifecycle::{self, RobotState};
+use crate::robot::{self, Robot, RobotPosition};
+use crate::roles::Drops;
+
+/// Modèle de fiabilité : les robots tombent en panne au hasard ou par usure.
+pub struct Reliability {
+    /// Probabilité de panne à chaque tick, que le robot avance, mine ou attende.
+    pub rate: f64,
+    /// Pas sans passer par la base avant une panne certaine par usure (0 : jamais).
+    pub wear: u32,
+    pub seed: u64,
+    rng: StdRng,
+}
+
+impl Reliability {
+    /// `none` ou `breakdowns[:rate=0.0005,wear=0,seed=...]`.
+    pub fn from_spec(spec: &str) -> Result<Option<Self>, String> {
+        let (name, mut params) = SpecParams::parse(spec)?;
+        let reliability = match name {
+            "none" => None,
+            "breakdowns" => {
+                let rate: f64 = params.take("rate", 0.0005)?;
+                if !(0.0..=1.0).contains(&rate) {
+                    return Err(format!("rate doit être entre 0 et 1: {rate}"));
+                }
+                let seed = params.take("seed", rand::random())?;
+                Some(Reliability {
+                    rate,
+                    wear: params.take("wear", 0)?,
+                    seed,
+                    rng: StdRng::seed_from_u64(seed),
+                })
+            }
+            other => {
+                return Err(format!(
+                    "modèle de pannes inconnu: {other} (disponibles: none, breakdowns)"
+                ));
+            }
+        };
+        params.finish(name)?;
+        Ok(reliability)
+    }
+
+    /// Compte les pas du tick et tire les pannes de chaque robot en activité, hors de la base.
+    /// Un robot en panne laisse sa cargaison
+    /// sur sa case et n'agit plus jusqu'à ce qu'un réparateur le ramène à la base.
+    /// Retourne le nombre de nouvelles pannes.
+    pub fn step(
+        &mut self,
+        robots: &mut [Robot],
+        previous: &HashMap<usize, RobotPosition>,
+        drops: &mut Drops,
+    ) -> u32 {
+        let mut breakdowns = 0;
+        let mut released = Vec::new();
+        for robot in robots.iter_mut() {
+            if robot.broken || robot.towed {
+                continue;
+            }
+            // Passage à la base : entretien, l'usure repart de zéro
+            if robot::at_base(robot) {
+                robot.moves = 0;
+                continue;
+            }
+            // Seuls les pas usent le robot ; une panne au hasard peut survenir à tout tick
+            if previous
+                .get(&robot.id)
+                .is_some_and(|&p| p != robot.position)
+            {
+                robot.moves += 1;
+            }
+            let worn_out = self.wear > 0 && robot.moves >= self.wear;
+            if !(worn_out || self.rng.gen_bool(self.rate)) {
+                continue;
+            }
+            robot.broken = true;
+            lifecycle::enter(robot, RobotState::Stuck);
+            breakdowns += 1;
+            // Un réparateur en panne lâche le robot qu'il remorquait
+            released.extend(robot.towing.take());
+            tracing::warn!(
+                "Robot {} en panne en ({}, {}) après {} pas",
+                robot.id,
+                robot.position.0,
+                robot.position.1,
+                robot.moves
+            );
+            if robot.collected_resources > 0
+                && let Some(resource) = robot.carried_resource.take()
+            {
+                let here = (robot.position.0, robot.position.1);
+                *drops.entry((here, resource)).or_insert(0) += robot.collected_resources;
+                robot.collected_resources = 0;
+            }
+        }
+        for robot in robots.iter_mut().filter(|r| released.contains(&r.id)) {
+            robot.towed = false;
+            if !robot.broken && robot.recovery.is_none() {
+                lifecycle::enter(robot, RobotState::Idle);
+            }
+        }
+        breakdowns
+    }
+}
@@ -0,0 +1,378 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+use crate::map::{Cell, Terrain};
+use crate::movement::{self, Movement};
+use crate::robot::{self, RobotPosition, RobotType};
+
+/// Distances depuis la base pour une famille de robots ayant les mêmes coûts de traversée.
+#[derive(Clone, Default)]
+struct DistanceMap {
+    /// Coût du trajet depuis la base jusqu'à chaque case connue atteignable.
+    dist: HashMap<(u16, u16), u32>,
+    /// Case précédente sur le plus court chemin depuis la base : le pas suivant vers la base.
+    parent: HashMap<(u16, u16), (u16, u16)>,
+}
+
+/// Champ de distance à la base sur les cases connues de l'essaim, partagé par tous les robots
+/// et tenu à jour case par case quand les connaissances changent.
+#[derive(Clone)]
+pub struct FlowField {
+    origin: (u16, u16),
+    movement: Movement,
+    /// Robots qui contournent l'eau, puis robots qui la passent à gué.
+    maps: [DistanceMap; 2],
+    /// Nombre de mises à jour reçues : change dès qu'une case connue change de coût.
+    revision: u64,
+}
+
+/// Type représentatif de chaque famille de `FlowField::maps`.
+const MOBILITIES: [RobotType; 2] = [RobotType::Collecteur, RobotType::Eclaireur];
+
+fn mobility(robot_type: RobotType) -> usize {
+    usize::from(Terrain::Water.move_cost(&robot_type).is_some())
+}
+
+/// Le changement d'une case connue modifie son coût de traversée pour l'une des familles :
+/// seuls ces changements sont à reporter dans le champ.
+pub fn changes_cost(before: Option<&Cell>, after: Option<&Cell>) -> bool {
+    MOBILITIES.iter().any(|robot_type| {
+        robot::known_cost(robot_type, before) != robot::known_cost(robot_type, after)
+    })
+}
+
+impl FlowField {
+    pub fn new(origin: RobotPosition, movement: Movement) -> Self {
+        FlowField {
+            origin: (origin.0, origin.1),
+            movement,
+            maps: Default::default(),
+            revision: 0,
+        }
+    }
+
+    /// Coût du trajet depuis la base, `None` si la case n'est pas reliée à la base par des cases connues.
+    pub fn distance(&self, robot_type: RobotType, position: RobotPosition) -> Option<u32> {
+        self.maps[mobility(robot_type)]
+            .dist
+            .get(&(position.0, position.1))
+            .copied()
+    }
+
+    /// Pas suivant vers la base depuis `position`.
+    pub fn next_step(
+        &self,
+        robot_type: RobotType,
+        position: RobotPosition,
+    ) -> Option<RobotPosition> {
+        self.maps[mobility(robot_type)]
+            .parent
+            .get(&(position.0, position.1))
+            .map(|&(x, y)| RobotPosition(x, y))
+    }
+
+    /// Nombre de pas du trajet vers la base en suivant le champ, quel que soit le terrain.
+    pub fn steps(&self, robot_type: RobotType, position: RobotPosition) -> Option<u32> {
+        let map = &self.maps[mobility(robot_type)];
+        let mut current = (position.0, position.1);
+        map.dist.get(&current)?;
+        let mut steps = 0;
+        while let Some(&next) = map.parent.get(&current) {
+            current = next;
+            steps += 1;
+        }
+        Some(steps)
+    }
+
+    /// Modèle de déplacement du champ.
+    pub fn movement(&self) -> Movement {
+        self.movement
+    }
+
+    /// La base du champ.
+    pub fn origin(&self) -> RobotPosition {
+        RobotPosition(self.origin.0, self.origin.1)
+    }
+
+    /// Version des connaissances de l'essaim vues par le champ (voir `planning::FailedSearch`).
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// Répercute les cases connues modifiées (découvertes, effondrées, creusées, éditées).
+    pub fn update(&mut self, known: &HashMap<(u16, u16), Cell>, changed: &[(u16, u16)]) {
+        if !changed.is_empty() {
+            self.revision += 1;
+        }
+        let (origin, movement) = (self.origin, self.movement);
+        for (distances, robot_type) in self.maps.iter_mut().zip(MOBILITIES) {
+            distances.update(known, changed, robot_type, origin, movement);
+        }
+    }
+}
+
+impl DistanceMap {
+    fn update(
+        &mut self,
+        known: &HashMap<(u16, u16), Cell>,
+        changed: &[(u16, u16)],
+        robot_type: RobotType,
+        origin: (u16, u16),
+        movement: Movement,
+    ) {
+        let cost = |pos: RobotPosition| {
+            known
+                .get(&(pos.0, pos.1))
+                .and_then(|cell| cell.move_cost(&robot_type))
+        };
+        let steps = |pos: (u16, u16)| {
+            movement
+                .steps(RobotPosition(pos.0, pos.1), cost)
+                .into_iter()
+                .map(|(next, weight)| ((next.0, next.1), weight))
+        };
+        let neighbours = |pos: (u16, u16)| {
+            movement
+                .neighbours(RobotPosition(pos.0, pos.1))
+                .map(|next| (next.0, next.1))
+        };
+        let mut heap = BinaryHeap::new();
+        if self.dist.insert(origin, 0).is_none() {
+            heap.push(Reverse((0, origin)));
+        }
+
+        // Une case modifiée perd sa distance, comme les cases dont le plus court chemin y
+        // passait ou longeait son coin en diagonale
+        let mut invalid = Vec::new();
+        let mut roots: Vec<(u16, u16)> = changed.to_vec();
+        for &pos in changed {
+            roots.extend(neighbours(pos).filter(|&next| {
+                self.parent.get(&next).is_some_and(|&from| {
+                    from.0 != next.0
+                        && from.1 != next.1
+                        && (pos == (from.0, next.1) || pos == (next.0, from.1))
+                })
+            }));
+        }
+        for pos in roots {
+            if pos == origin {
+                continue;
+            }
+            let mut stack = vec![pos];
+            while let Some(current) = stack.pop() {
+                if self.dist.remove(&current).is_none() {
+                    continue;
+                }
+                self.parent.remove(&current);
+                invalid.push(current);
+                stack.extend(neighbours(current).filter(|n| self.parent.get(n) == Some(&current)));
+            }
+        }
+
+        // Ces cases repartent de leurs voisins encore reliés à la base ; autour d'une case
+        // modifiée, un pas en diagonale a pu s'ouvrir
+        let reseeded = changed
+            .iter()
+            .flat_map(|&pos| std::iter::once(pos).chain(neighbours(pos)));
+        for pos in invalid.iter().copied().chain(reseeded) {
+            if pos == origin {
+                continue;
+            }
+            let Some(entry) = cost(RobotPosition(pos.0, pos.1)) else {
+                continue;
+            };
+            // Les pas sont symétriques, mais leur poids dépend de la case d'arrivée : ici `pos`
+            let best = steps(pos)
+                .filter_map(|(from, _)| {
+                    let weight = movement::step_weight(from, pos) * entry as u32;
+                    self.dist.get(&from).map(|&d| (d + weight, from))
+                })
+                .min();
+            if let Some((d, from)) = best
+                && self.dist.get(&pos).is_none_or(|&current| d < current)
+            {
+                self.dist.insert(pos, d);
+                self.parent.insert(pos, from);
+                heap.push(Reverse((d, pos)));
+            }
+        }
+
+        // Propagation de Dijkstra depuis les cases mises à jour
+        while let Some(Reverse((d, pos))) = heap.pop() {
+            if self.dist.get(&pos).is_some_and(|&current| d > current) {
+                continue;
+            }
+            for (next, weight) in steps(pos) {
+                let candidate = d + weight;
+                if next != origin
+                    && self
+                        .dist
+                        .get(&next)
+                        .is_none_or(|&current| candidate < current)
+                {
+                    self.dist.insert(next, candidate);
+                    self.parent.insert(next, pos);
+                    heap.push(Reverse((candidate, next)));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pathfinding::prelude::dijkstra_all;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    const SIZE: u16 = 14;
+    const ORIGIN: (u16, u16) = (7, 7);
+    const TERRAINS: [Terrain; 5] = [
+        Terrain::Floor,
+        Terrain::Wall,
+        Terrain::Sand,
+        Terrain::Water,
+        Terrain::Rubble,
+    ];
+
+    /// Case connue au hasard, ou inconnue (`None`).
+    fn random_cell(rng: &mut StdRng) -> Option<Cell> {
+        if rng.gen_bool(0.1) {
+            return None;
+        }
+        // Le sol domine pour que la base reste reliée à une bonne partie de la carte
+        let terrain = if rng.gen_bool(0.5) {
+            Terrain::Floor
+        } else {
+            TERRAINS[rng.gen_range(0..TERRAINS.len())]
+        };
+        Some(Cell::new(terrain))
+    }
+
+    /// Distances recalculées de zéro par un Dijkstra sur les mêmes connaissances.
+    fn fresh_distances(
+        known: &HashMap<(u16, u16), Cell>,
+        robot_type: RobotType,
+        movement: Movement,
+    ) -> HashMap<(u16, u16), u32> {
+        let cost = |p: RobotPosition| {
+            known
+                .get(&(p.0, p.1))
+                .and_then(|cell| cell.move_cost(&robot_type))
+        };
+        let origin = RobotPosition(ORIGIN.0, ORIGIN.1);
+        let mut distances: HashMap<(u16, u16), u32> =
+            dijkstra_all(&origin, |p| movement.steps(*p, cost))
+                .into_iter()
+                .map(|(p, (_, d))| ((p.0, p.1), d))
+                .collect();
+        distances.insert(ORIGIN, 0);
+        distances
+    }
+
+    /// Le champ tenu à jour donne les mêmes distances qu'un calcul complet, et chaque pas
+    /// suivant mène à une case plus proche de la base du coût exact du pas.
+    fn assert_matches_fresh(field: &FlowField, known: &HashMap<(u16, u16), Cell>) {
+        for robot_type in MOBILITIES {
+            let fresh = fresh_distances(known, robot_type, field.movement);
+            for y in 0..SIZE {
+                for x in 0..SIZE {
+                    let position = RobotPosition(x, y);
+                    let distance = field.distance(robot_type, position);
+                    assert_eq!(
+                        distance,
+                        fresh.get(&(x, y)).copied(),
+                        "{robot_type:?} {:?} en ({x}, {y})",
+                        field.movement
+                    );
+                    if let (Some(distance), Some(next)) =
+                        (distance, field.next_step(robot_type, position))
+                    {
+                        let entry = known[&(x, y)].move_cost(&robot_type).unwrap() as u32;
+                        let weight = movement::step_weight((next.0, next.1), (x, y)) * entry;
+                        assert_eq!(field.distance(robot_type, next), Some(distance - weight));
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn incremental_update_matches_fresh_dijkstra() {
+        for movement in [Movement::Cardinal, Movement::Octile] {
+            let mut rng = StdRng::seed_from_u64(7);
+            let mut known = HashMap::new();
+            for y in 0..SIZE {
+                for x in 0..SIZE {
+                    if let Some(cell) = random_cell(&mut rng) {
+                        known.insert((x, y), cell);
+                    }
+                }
+            }
+            known.insert(ORIGIN, Cell::base());
+            let mut field = FlowField::new(RobotPosition(ORIGIN.0, ORIGIN.1), movement);
+            let all: Vec<(u16, u16)> = known.keys().copied().collect();
+            field.update(&known, &all);
+            assert_matches_fresh(&field, &known);
+
+            // Effondrements, creusements, terrain changé, cases oubliées ou découvertes
+            for _ in 0..300 {
+                let mut changed = Vec::new();
+                for _ in 0..rng.gen_range(1..=3) {
+                    let position = (rng.gen_range(0..SIZE), rng.gen_range(0..SIZE));
+                    if position == ORIGIN {
+                        continue;
+                    }
+                    match random_cell(&mut rng) {
+                        Some(cell) => known.insert(position, cell),
+                        None => known.remove(&position),
+                    };
+                    changed.push(position);
+                }
+                field.update(&known, &changed);
+                assert_matches_fresh(&field, &known);
+            }
+        }
+    }
+
+    #[test]
+    fn unknown_cells_are_out_of_the_field() {
+        let mut known = HashMap::from([
+            (ORIGIN, Cell::base()),
+            ((8, 7), Cell::new(Terrain::Floor)),
+            ((10, 7), Cell::new(Terrain::Floor)),
+        ]);
+        let mut field = FlowField::new(RobotPosition(ORIGIN.0, ORIGIN.1), Movement::Cardinal);
+        field.update(&known, &[(8, 7), (10, 7)]);
+        let collector = RobotType::Collecteur;
+        assert_eq!(field.distance(collector, RobotPosition(8, 7)), Some(10));
+        assert_eq!(field.distance(collector, RobotPosition(10, 7)), None);
+
+        // La case qui manquait relie la plus lointaine à la base
+        known.insert((9, 7), Cell::new(Terrain::Sand));
+        field.update(&known, &[(9, 7)]);
+        assert_eq!(field.distance(collector, RobotPosition(10, 7)), Some(40));
+        assert_eq!(
+            field.next_step(collector, RobotPosition(10, 7)),
+            Some(RobotPosition(9, 7))
+        );
+        // Le sable alourdit le coût, pas le nombre de pas
+        assert_eq!(field.steps(collector, RobotPosition(10, 7)), Some(3));
+        assert_eq!(field.steps(collector, RobotPosition(11, 7)), None);
+
+        // Une mise à jour sans case changée laisse la version des connaissances intacte
+        let revision = field.revision();
+        field.update(&known, &[]);
+        assert_eq!(field.revision(), revision);
+
+        // L'eau coupe le chemin des collecteurs, pas celui des éclaireurs
+        known.insert((9, 7), Cell::new(Terrain::Water));
+        field.update(&known, &[(9, 7)]);
+        assert_eq!(field.distance(collector, RobotPosition(10, 7)), None);
+        assert_eq!(
+            field.distance(RobotType::Eclaireur, RobotPosition(10, 7)),
+            Some(50)
+        );
+    }
+}
@@ -1,51 +1,528 @@
-use crate::{base, map, robot};
+use crate::lifecycle::RobotState;
+use crate::resources::REGISTRY;
+use crate::{
+    base, cooperative, docking, dynamics, events, failures, flow_field, lifecycle, map, movement,
+    planning, recovery, robot, roles, stats, upgrades,
+};
 use std::collections::{HashMap, HashSet};
 use std::sync::{Arc, Mutex};
 use std::thread;
 use tokio::sync::mpsc;
 
 pub struct GameState {
-    pub(crate) map: Vec<Vec<map::Tile>>,
+    pub(crate) map: Vec<Vec<map::Cell>>,
     pub(crate) width: u16,
     pub(crate) height: u16,
     pub(crate) robots: Vec<robot::Robot>,
-    map_discovered: HashMap<(u16, u16), map::Tile>,
+    pub(crate) base_center: robot::RobotPosition,
+    pub(crate) map_discovered: Arc<HashMap<(u16, u16), map::Cell>>,
+    /// Distances à la base sur les cases connues, partagées par tous les robots.
+    pub(crate) home_field: Arc<flow_field::FlowField>,
+    /// Cases connues dont le coût de traversée a changé depuis la dernière mise à jour du champ.
+    known_changes: Vec<(u16, u16)>,
     _base: base::SharedBase,
-    pub energy: u32,
-    pub crystals: u32,
+    /// Totaux livrés par type de ressource, indexés comme `resources::REGISTRY`.
+    pub totals: Vec<u32>,
+    pub score: u32,
     pub rx_broadcast: tokio::sync::broadcast::Receiver<base::BroadcastMessage>,
     pub tx_base: mpsc::Sender<base::BaseMessage>,
     pub last_visited: HashMap<(u16, u16), usize>,
     pub pending_resources: HashSet<(u16, u16)>,
+    pub tick: u64,
+    pub visit_ticks: HashMap<(u16, u16), u64>,
+    pub stats: stats::StatsHistory,
+    /// Gisements séparés de la base par des murs, ignorés pour la fin de partie.
+    pub unreachable_deposits: Vec<(u16, u16)>,
+    /// Gisements reliés à la base mais trop loin pour un aller-retour batterie pleine,
+    /// ignorés pour la fin de partie.
+    pub out_of_range_deposits: Vec<(u16, u16)>,
+    /// Régénération et apparition de gisements (`--dynamics`), absente par défaut.
+    pub dynamics: Option<dynamics::ResourceDynamics>,
+    /// Effondrements, érosion et zones dangereuses (`--events`), absents par défaut.
+    pub events: Option<events::WorldEvents>,
+    /// Cargaisons laissées loin de la base par les collecteurs, en attente d'un transporteur.
+    pub drops: roles::Drops,
+    /// Améliorations achetées par la base, installées sur chaque robot à son passage.
+    pub upgrades: upgrades::Upgrades,
+    pub spent_crystals: u32,
+    /// Pannes des robots (`--failures`), absentes par défaut.
+    pub reliability: Option<failures::Reliability>,
+    /// Pas au hasard des robots bloqués (`--recovery`), tirés de leur propre graine.
+    pub random_walk: recovery::RandomWalk,
+    /// Pannes survenues depuis le début de la partie.
+    pub breakdowns: u32,
+    /// Gisements qu'un collecteur bloqué n'a pas pu atteindre, écartés jusqu'au tick indiqué.
+    pub blocked_deposits: HashMap<(u16, u16), u64>,
+    /// Blocages détectés depuis le début de la partie (voir `recovery`).
+    pub stuck_incidents: u32,
+    /// Planification des trajets des collecteurs (`--planner`), indépendante par défaut.
+    pub planner: planning::Planner,
+    /// Table de réservation du planificateur coopératif.
+    reservations: cooperative::Reservations,
+    /// Postes d'amarrage de la base et file des robots chargés.
+    pub docking: docking::Docking,
+    /// Ticks × cases hors de la base où plusieurs collecteurs se sont trouvés ensemble.
+    pub conflicts: u32,
 }
 
 impl GameState {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
-        map: Vec<Vec<map::Tile>>,
+        map: Vec<Vec<map::Cell>>,
         width: u16,
         height: u16,
         robots: Vec<robot::Robot>,
+        base_center: robot::RobotPosition,
         base: base::SharedBase,
         rx_broadcast: tokio::sync::broadcast::Receiver<base::BroadcastMessage>,
         tx_base: mpsc::Sender<base::BaseMessage>,
     ) -> Self {
+        let mut robots = robots;
+        for (id, robot) in robots.iter_mut().enumerate() {
+            robot.id = id;
+        }
         Self {
             map,
             width,
             height,
             robots,
-            map_discovered: HashMap::new(),
+            base_center,
+            map_discovered: Arc::new(HashMap::new()),
+            home_field: Arc::new(flow_field::FlowField::new(
+                base_center,
+                movement::Movement::default(),
+            )),
+            known_changes: Vec::new(),
             _base: base,
-            energy: 0,
-            crystals: 0,
+            totals: vec![0; REGISTRY.len()],
+            score: 0,
             rx_broadcast,
             tx_base,
             last_visited: HashMap::new(),
             pending_resources: HashSet::new(),
+            tick: 0,
+            visit_ticks: HashMap::new(),
+            stats: stats::StatsHistory::default(),
+            unreachable_deposits: Vec::new(),
+            out_of_range_deposits: Vec::new(),
+            dynamics: None,
+            events: None,
+            drops: HashMap::new(),
+            upgrades: upgrades::Upgrades::default(),
+            spent_crystals: 0,
+            reliability: None,
+            random_walk: recovery::RandomWalk::default(),
+            breakdowns: 0,
+            blocked_deposits: HashMap::new(),
+            stuck_incidents: 0,
+            planner: planning::Planner::default(),
+            reservations: cooperative::Reservations::default(),
+            docking: docking::Docking::new(
+                base_center,
+                docking::DEFAULT_DOCKS,
+                docking::DEFAULT_UNLOAD_TICKS,
+            ),
+            conflicts: 0,
+        }
+    }
+
+    /// Choisit le modèle de déplacement de tous les robots et recalcule le champ de distance à la base.
+    pub fn set_movement(&mut self, movement: movement::Movement) {
+        for robot in &mut self.robots {
+            robot.movement = movement;
+            robot.path = None;
+        }
+        self.home_field = Arc::new(flow_field::FlowField::new(self.base_center, movement));
+        self.known_changes = self.map_discovered.keys().copied().collect();
+        self.update_home_field();
+    }
+
+    /// Recense les gisements inaccessibles depuis la base.
+    pub fn detect_unreachable_deposits(&mut self) {
+        self.unreachable_deposits =
+            map::unreachable_deposits(&self.map, (self.base_center.0, self.base_center.1));
+        if !self.unreachable_deposits.is_empty() {
+            tracing::warn!(
+                "{} gisements inaccessibles: {:?}",
+                self.unreachable_deposits.len(),
+                self.unreachable_deposits
+            );
+        }
+        self.detect_out_of_range_deposits();
+    }
+
+    /// Recense les gisements dont l'aller-retour depuis la base, par le chemin que suivrait un
+    /// collecteur sur le monde réel, dépasse la batterie de la flotte (améliorations comprises).
+    fn detect_out_of_range_deposits(&mut self) {
+        let world: HashMap<(u16, u16), map::Cell> = self
+            .map
+            .iter()
+            .enumerate()
+            .flat_map(|(y, row)| {
+                row.iter()
+                    .enumerate()
+                    .map(move |(x, &cell)| ((x as u16, y as u16), cell))
+            })
+            .collect();
+        let mut field = flow_field::FlowField::new(self.base_center, self.home_field.movement());
+        field.update(&world, &world.keys().copied().collect::<Vec<_>>());
+        let capacity = self.upgrades.energy_capacity();
+        self.out_of_range_deposits = world
+            .iter()
+            .filter(|(_, cell)| cell.deposit_left().is_some())
+            .filter(|&(&(x, y), _)| {
+                field
+                    .steps(robot::RobotType::Collecteur, robot::RobotPosition(x, y))
+                    .is_some_and(|steps| !robot::round_trip_fits(capacity, steps))
+            })
+            .map(|(&position, _)| position)
+            .collect();
+        self.out_of_range_deposits.sort_unstable();
+        if !self.out_of_range_deposits.is_empty() {
+            tracing::warn!(
+                "{} gisements hors de portée de la batterie ({}): {:?}",
+                self.out_of_range_deposits.len(),
+                capacity,
+                self.out_of_range_deposits
+            );
+        }
+    }
+
+    /// Plus aucun gisement accessible et minable par un collecteur, et plus rien en transit.
+    /// Jamais vrai quand les gisements se régénèrent ou apparaissent.
+    pub fn is_finished(&self) -> bool {
+        if self.dynamics.is_some() {
+            return false;
+        }
+        // Un robot en panne ne compte plus si aucun réparateur ne peut le ramener
+        let repairable = self
+            .robots
+            .iter()
+            .any(|r| r.robot_type == robot::RobotType::Reparateur && !r.broken);
+        let available = |robot: &&robot::Robot| !robot.broken || repairable;
+        let deposits_left = self.map.iter().enumerate().any(|(y, row)| {
+            row.iter().enumerate().any(|(x, cell)| {
+                cell.deposit_left().is_some_and(|deposit| {
+                    self.robots
+                        .iter()
+                        .filter(available)
+                        .any(|robot| robot::can_mine(robot, deposit.resource))
+                }) && !self.unreachable_deposits.contains(&(x as u16, y as u16))
+                    && !self.out_of_range_deposits.contains(&(x as u16, y as u16))
+            })
+        });
+        // Sans transporteur, la cargaison d'un robot en panne reste au sol
+        let has_haulers = self
+            .robots
+            .iter()
+            .filter(available)
+            .any(|r| r.robot_type == robot::RobotType::Transporteur);
+        !deposits_left
+            && (self.drops.is_empty() || !has_haulers)
+            && self.robots.iter().all(|r| r.collected_resources == 0)
+    }
+
+    pub fn summary(&self) -> String {
+        let totals: Vec<String> = REGISTRY
+            .iter()
+            .zip(&self.totals)
+            .map(|(kind, total)| format!("{} {}", kind.key, total))
+            .collect();
+        // Ticks de robot passés dans chaque état, pour comparer les parties après coup
+        let state_ticks: Vec<String> = RobotState::ALL
+            .iter()
+            .zip(self.stats.state_ticks)
+            .map(|(state, ticks)| format!("{} {}", state.name(), ticks))
+            .collect();
+        format!(
+            "Simulation terminée en {} ticks: {}, score {}, pannes {}, blocages {}, conflits {}, livraisons {} (refusées {}, perdues {}), transitions {}, ticks par état ({}), gisements inaccessibles {:?}, hors de portée {:?}",
+            self.tick,
+            totals.join(", "),
+            self.score,
+            self.breakdowns,
+            self.stuck_incidents,
+            self.conflicts,
+            self.docking.stats.confirmed,
+            self.docking.stats.rejected,
+            self.docking.stats.lost,
+            self.stats.transitions,
+            state_ticks.join(", "),
+            self.unreachable_deposits,
+            self.out_of_range_deposits
+        )
+    }
+
+    /// Remplace une case du monde (édition manuelle) et oublie ce que les robots en savaient.
+    pub fn set_cell(&mut self, x: u16, y: u16, cell: map::Cell) {
+        if self.map[y as usize][x as usize].is_base() {
+            tracing::warn!("Impossible de modifier la base en ({}, {})", x, y);
+            return;
+        }
+        if cell.terrain == map::Terrain::Wall
+            && self
+                .robots
+                .iter()
+                .any(|r| r.position == robot::RobotPosition(x, y))
+        {
+            tracing::warn!("Un robot occupe ({}, {}), mur refusé", x, y);
+            return;
+        }
+        tracing::info!("Édition: ({}, {}) -> {:?}", x, y, cell);
+        self.map[y as usize][x as usize] = cell;
+        if let Some(dynamics) = &mut self.dynamics {
+            dynamics.forget(x, y);
+        }
+        if Arc::make_mut(&mut self.map_discovered)
+            .remove(&(x, y))
+            .is_some()
+        {
+            self.known_changes.push((x, y));
+        }
+        self.pending_resources.remove(&(x, y));
+        for robot in &mut self.robots {
+            robot.map_discovered.remove(&(x, y));
+        }
+        // Un mur peut isoler des gisements, un sol en rouvrir l'accès
+        self.detect_unreachable_deposits();
+    }
+
+    /// Chaque robot regarde autour de lui : une case connue qui a changé (effondrement,
+    /// érosion, creusement, zone dangereuse) est corrigée dans les connaissances de l'essaim,
+    /// en gardant ce qu'il savait de son gisement. Une case que personne ne voit garde son
+    /// ancien état jusqu'à ce qu'un robot repasse à côté.
+    fn observe_changes(&mut self) {
+        // Case vue et robots qui la voient
+        let mut seen: HashMap<(u16, u16), (map::Cell, Vec<usize>)> = HashMap::new();
+        for (index, robot) in self.robots.iter().enumerate() {
+            for (position, truth) in
+                robot::perceived_cells(robot, &self.map, self.width, self.height)
+            {
+                seen.entry(position)
+                    .or_insert((truth, Vec::new()))
+                    .1
+                    .push(index);
+            }
+        }
+        for (position, (truth, watchers)) in seen {
+            let Some(known) = self.map_discovered.get(&position) else {
+                continue;
+            };
+            let updated = map::Cell {
+                deposit: known.deposit,
+                ..truth
+            };
+            if *known == updated {
+                continue;
+            }
+            if flow_field::changes_cost(Some(known), Some(&updated)) {
+                self.known_changes.push(position);
+            }
+            Arc::make_mut(&mut self.map_discovered).insert(position, updated);
+            // Les observations propres des robots qui la voient aussi
+            for index in watchers {
+                if let Some(own) = self.robots[index].map_discovered.get_mut(&position) {
+                    *own = map::Cell {
+                        deposit: own.deposit,
+                        ..truth
+                    };
+                }
+            }
+        }
+        self.update_home_field();
+    }
+
+    /// Reporte dans le champ de distance à la base les cases connues modifiées depuis le dernier appel.
+    fn update_home_field(&mut self) {
+        if self.known_changes.is_empty() {
+            return;
+        }
+        let changed = std::mem::take(&mut self.known_changes);
+        Arc::make_mut(&mut self.home_field).update(&self.map_discovered, &changed);
+    }
+
+    /// Applique les messages diffusés par la base.
+    pub fn handle_broadcasts(&mut self) {
+        while let Ok(msg) = self.rx_broadcast.try_recv() {
+            match msg {
+                base::BroadcastMessage::BaseStats { totals, score } => {
+                    self.totals = totals;
+                    self.score = score;
+                }
+                base::BroadcastMessage::Depleted { position } => self.forget_deposit(position),
+                base::BroadcastMessage::Upgraded { upgrades, spent } => {
+                    let longer_range = upgrades.energy != self.upgrades.energy;
+                    self.upgrades = upgrades;
+                    self.spent_crystals = spent;
+                    if longer_range {
+                        self.detect_out_of_range_deposits();
+                    }
+                }
+            }
+        }
+    }
+
+    /// Retire un gisement épuisé des connaissances de l'essaim et de chaque robot.
+    fn forget_deposit(&mut self, position: (u16, u16)) {
+        if let Some(known) = Arc::make_mut(&mut self.map_discovered).get_mut(&position) {
+            known.deposit = None;
+        }
+        for robot in &mut self.robots {
+            if let Some(known) = robot.map_discovered.get_mut(&position) {
+                known.deposit = None;
+            }
+        }
+    }
+
+    /// Téléporte un robot sur une case franchissable.
+    pub fn relocate_robot(&mut self, index: usize, x: u16, y: u16) {
+        if let Some(robot) = self.robots.get_mut(index) {
+            if !self.map[y as usize][x as usize].is_passable(&robot.robot_type) {
+                tracing::warn!("Case ({}, {}) bloquée, robot non déplacé", x, y);
+                return;
+            }
+            tracing::info!("Robot {} déplacé en ({}, {})", index, x, y);
+            robot.position = robot::RobotPosition(x, y);
+        }
+    }
+
+    /// Robot qui ne progresse plus : sa cible et le gisement qu'il devait signaler sont rendus
+    /// à l'essaim. Un robot bloqué tente ensuite de se dégager (voir `recovery`), un robot en
+    /// panne attend un réparateur.
+    fn handle_stalled(&mut self) {
+        for robot in &mut self.robots {
+            let stuck = recovery::is_stuck(robot);
+            let stranded = roles::is_stranded(robot) && robot.target_resource.is_some();
+            if !stuck && !stranded {
+                continue;
+            }
+            if let Some(target) = robot.target_resource.take() {
+                match robot.robot_type {
+                    robot::RobotType::Eclaireur => {
+                        self.pending_resources.remove(&(target.0, target.1));
+                        robot.found_resource = None;
+                    }
+                    // Hors d'atteinte pour l'instant : plus proposé aux collecteurs quelque temps
+                    robot::RobotType::Collecteur if stuck => {
+                        self.blocked_deposits
+                            .insert((target.0, target.1), self.tick + recovery::BLOCKED_FOR);
+                    }
+                    _ => {}
+                }
+            }
+            if stuck {
+                self.stuck_incidents += 1;
+                tracing::warn!(
+                    "Robot {} bloqué en ({}, {}) depuis {} ticks, tentative de dégagement",
+                    robot.id,
+                    robot.position.0,
+                    robot.position.1,
+                    robot.idle_ticks
+                );
+                recovery::start(robot, &self.random_walk);
+            } else {
+                tracing::warn!(
+                    "Robot {} sans progrès depuis {} ticks, tâche réattribuée",
+                    robot.id,
+                    robot.idle_ticks
+                );
+            }
+        }
+    }
+
+    /// Réparateurs : rejoignent le robot immobilisé le plus proche et le remorquent
+    /// jusqu'à la base, où il reprend sans tâche.
+    fn repair(&mut self) {
+        for index in 0..self.robots.len() {
+            let repairer = &mut self.robots[index];
+            if repairer.robot_type != robot::RobotType::Reparateur
+                || repairer.state == RobotState::Stuck
+                || robot::wait_on_terrain(repairer)
+            {
+                continue;
+            }
+            let previous_position = repairer.position;
+
+            if repairer.towing.is_none() && repairer.state == RobotState::Charging {
+                robot::return_to_charge(repairer, &self.map_discovered, &self.home_field);
+                robot::apply_terrain_cost(repairer, previous_position, &self.map);
+                continue;
+            }
+            if let Some(towed_id) = repairer.towing {
+                robot::go_home(repairer, &self.map_discovered, &self.home_field);
+                let position = repairer.position;
+                let at_home = position == repairer.home;
+                if at_home {
+                    repairer.towing = None;
+                    lifecycle::enter(repairer, RobotState::Idle);
+                }
+                if let Some(towed) = self.robots.iter_mut().find(|r| r.id == towed_id) {
+                    towed.position = position;
+                    if at_home {
+                        towed.towed = false;
+                        towed.idle_ticks = 0;
+                        towed.target_resource = None;
+                        towed.recovery = None;
+                        lifecycle::enter(towed, RobotState::Idle);
+                        tracing::info!("Robot {} ramené à la base", towed_id);
+                        if towed.broken {
+                            towed.broken = false;
+                            towed.moves = 0;
+                            tracing::info!("Robot {} réparé", towed_id);
+                        }
+                    }
+                }
+            } else {
+                let here = repairer.position;
+                let repairer_id = repairer.id;
+                let stranded = self
+                    .robots
+                    .iter()
+                    .filter(|r| r.id != repairer_id && roles::is_stranded(r))
+                    .min_by_key(|r| r.position.0.abs_diff(here.0) + r.position.1.abs_diff(here.1))
+                    .map(|r| (r.id, r.position));
+                let repairer = &mut self.robots[index];
+                match stranded {
+                    Some((id, position)) if position == here => {
+                        repairer.target_resource = None;
+                        repairer.towing = Some(id);
+                        lifecycle::enter(repairer, RobotState::ReturningWithCargo);
+                        if let Some(towed) = self.robots.iter_mut().find(|r| r.id == id) {
+                            towed.towed = true;
+                            lifecycle::enter(towed, RobotState::Stuck);
+                        }
+                        tracing::info!("Réparateur {}: remorque le robot {}", repairer_id, id);
+                    }
+                    Some((_, position)) => {
+                        repairer.target_resource = Some(position);
+                        lifecycle::enter(repairer, RobotState::GoingToDeposit);
+                        robot::go_to_nearest_point(
+                            repairer,
+                            position,
+                            &self.map_discovered,
+                            self.home_field.revision(),
+                        );
+                    }
+                    None => {
+                        repairer.target_resource = None;
+                        lifecycle::enter(repairer, RobotState::Idle);
+                        robot::go_home(repairer, &self.map_discovered, &self.home_field);
+                    }
+                }
+            }
+
+            robot::apply_terrain_cost(&mut self.robots[index], previous_position, &self.map);
         }
     }
 
     pub fn update(&mut self) {
+        self.tick += 1;
+        // Avant d'agir, chaque robot voit les changements autour de lui
+        self.observe_changes();
+        let previous_positions: HashMap<usize, robot::RobotPosition> =
+            self.robots.iter().map(|r| (r.id, r.position)).collect();
+        let previous_states: HashMap<usize, RobotState> =
+            self.robots.iter().map(|r| (r.id, r.state)).collect();
+
         // Collecter les positions des éclaireurs
         let eclaireur_positions: HashSet<(u16, u16)> = self
             .robots
@@ -54,8 +531,8 @@ impl GameState {
             .map(|r| (r.position.0, r.position.1))
             .collect();
 
-        // Données partagées entre threads (avec Arc + Mutex)
-        let map_shared = Arc::new(Mutex::new(self.map.clone()));
+        // Données partagées entre threads (avec Arc + Mutex) ; les éclaireurs ne modifient pas le monde
+        let map_shared = Arc::new(self.map.clone());
         let last_visited_shared = Arc::new(Mutex::new(self.last_visited.clone()));
         let pending_shared = Arc::new(Mutex::new(self.pending_resources.clone()));
 
@@ -80,10 +557,18 @@ impl GameState {
                 let last_visited_clone = Arc::clone(&last_visited_shared);
                 let pending_clone = Arc::clone(&pending_shared);
                 let eclaireur_pos = eclaireur_positions.clone();
+                let home_field = Arc::clone(&self.home_field);
+                let known = Arc::clone(&self.map_discovered);
                 let width = self.width;
                 let height = self.height;
 
                 thread::spawn(move || {
+                    // Un éclaireur remorqué suit son réparateur, un éclaireur en panne attend ;
+                    // le dégagement est joué avec les autres robots
+                    let mut observations = Vec::new();
+                    if robot.state == RobotState::Stuck {
+                        return (robot, observations);
+                    }
                     let other_positions: HashSet<(u16, u16)> = eclaireur_pos
                         .iter()
                         .filter(|&&pos| pos != (robot.position.0, robot.position.1))
@@ -97,39 +582,41 @@ impl GameState {
                     }
 
                     // Appeler move_robot avec les locks
-                    {
-                        let mut map = map_clone.lock().unwrap();
-                        let lv = last_visited_clone.lock().unwrap();
-                        let mut pending = pending_clone.lock().unwrap();
-
-                        robot::move_robot(
-                            &mut robot,
-                            &mut map,
-                            width,
-                            height - 1,
-                            &other_positions,
-                            &lv,
-                            robot_id,
-                            &mut pending,
-                        );
+                    if !robot::wait_on_terrain(&mut robot) {
+                        let before = robot.position;
+                        if robot.state == RobotState::Charging {
+                            robot::return_to_charge(&mut robot, &known, &home_field);
+                        } else {
+                            let lv = last_visited_clone.lock().unwrap();
+                            let mut pending = pending_clone.lock().unwrap();
+                            observations = robot::move_robot(
+                                &mut robot,
+                                &map_clone,
+                                width,
+                                height,
+                                &other_positions,
+                                &lv,
+                                robot_id,
+                                &mut pending,
+                                &known,
+                                &home_field,
+                            );
+                        }
+                        robot::apply_terrain_cost(&mut robot, before, &map_clone);
                     }
 
-                    robot
+                    (robot, observations)
                 })
             })
             .collect();
 
         // Attendre que tous les threads se terminent
-        let mut eclaireurs: Vec<_> = handles
+        let (mut eclaireurs, observations): (Vec<_>, Vec<_>) = handles
             .into_iter()
             .map(|h| h.join().expect("Thread éclaireur a paniqué"))
-            .collect();
+            .unzip();
 
         // Récupérer les données partagées
-        self.map = Arc::try_unwrap(map_shared)
-            .expect("Arc still has references")
-            .into_inner()
-            .unwrap();
         self.last_visited = Arc::try_unwrap(last_visited_shared)
             .expect("Arc still has references")
             .into_inner()
@@ -139,11 +626,16 @@ impl GameState {
             .into_inner()
             .unwrap();
 
-        // Mettre à jour map_discovered avec les découvertes de chaque éclaireur
-        for robot in &eclaireurs {
-            self.map_discovered
-                .extend(robot.map_discovered.iter().map(|(x, y)| (*x, y.clone())));
+        // Mettre à jour map_discovered avec les cases observées par les éclaireurs ce tick ;
+        // les threads ont rendu leur référence, la mise à jour se fait sans copie
+        let known = Arc::make_mut(&mut self.map_discovered);
+        for (position, cell) in observations.into_iter().flatten() {
+            let before = known.insert(position, cell);
+            if flow_field::changes_cost(before.as_ref(), Some(&cell)) {
+                self.known_changes.push(position);
+            }
         }
+        self.update_home_field();
 
         // Remettre les robots dans la liste
         self.robots.append(&mut eclaireurs);
@@ -157,68 +649,254 @@ impl GameState {
             .filter_map(|r| r.target_resource)
             .map(|pos| (pos.0, pos.1))
             .collect();
+        // Un gisement qu'un collecteur bloqué n'a pas atteint est écarté quelque temps
+        let tick = self.tick;
+        self.blocked_deposits.retain(|_, until| *until > tick);
+        reserved_positions.extend(self.blocked_deposits.keys());
+
+        let has_haulers = self
+            .robots
+            .iter()
+            .any(|r| r.robot_type == robot::RobotType::Transporteur);
+        let mut hauled_drops: HashSet<(u16, u16)> = self
+            .robots
+            .iter()
+            .filter(|r| r.robot_type == robot::RobotType::Transporteur)
+            .filter_map(|r| r.target_resource)
+            .map(|pos| (pos.0, pos.1))
+            .collect();
+        let mut building_sites: HashSet<(u16, u16)> = self
+            .robots
+            .iter()
+            .filter(|r| r.robot_type == robot::RobotType::Batisseur)
+            .filter_map(|r| r.target_resource)
+            .map(|pos| (pos.0, pos.1))
+            .collect();
+        let mut dug = Vec::new();
+        self.reservations.expire(self.tick);
+        self.docking.update(&mut self.robots, &self.tx_base);
 
         for robot in &mut self.robots {
-            robot::get_discovered_map(robot, &self.map_discovered);
+            // En panne ou remorqué, le robot attend ; en dégagement, il suit `recovery`
+            if robot.state == RobotState::Stuck {
+                if robot.recovery.is_some() && !robot::wait_on_terrain(robot) {
+                    let previous_position = robot.position;
+                    recovery::step(
+                        robot,
+                        &self.map,
+                        &self.map_discovered,
+                        &self.home_field,
+                        &mut self.random_walk,
+                    );
+                    robot::apply_terrain_cost(robot, previous_position, &self.map);
+                }
+                continue;
+            }
+
+            let support_role = matches!(
+                robot.robot_type,
+                robot::RobotType::Transporteur | robot::RobotType::Batisseur
+            );
+            if support_role && !robot::wait_on_terrain(robot) {
+                let previous_position = robot.position;
+                if robot.state == RobotState::Charging {
+                    // La cargaison est livrée avant la recharge
+                    if !robot::deliver_cargo(
+                        robot,
+                        &mut self.docking,
+                        &self.map_discovered,
+                        &self.home_field,
+                        None,
+                        &self.tx_base,
+                    ) {
+                        robot::return_to_charge(robot, &self.map_discovered, &self.home_field);
+                    }
+                } else if robot.robot_type == robot::RobotType::Transporteur {
+                    roles::haul(
+                        robot,
+                        &mut self.drops,
+                        &mut hauled_drops,
+                        &self.map_discovered,
+                        &self.home_field,
+                        &mut self.docking,
+                        &self.tx_base,
+                    );
+                } else {
+                    dug.extend(roles::build(
+                        robot,
+                        &mut self.map,
+                        &self.unreachable_deposits,
+                        &mut building_sites,
+                        &self.map_discovered,
+                        &self.home_field,
+                    ));
+                }
+                robot::apply_terrain_cost(robot, previous_position, &self.map);
+            }
 
             if robot.robot_type == robot::RobotType::Collecteur {
-                if robot.target_resource.is_none() {
-                    for ((x, y), _tile) in self.map_discovered.clone() {
-                        match self.map[y as usize][x as usize] {
-                            map::Tile::Explored => {
-                                self.map_discovered.insert((x, y), map::Tile::Explored);
-                            }
-                            map::Tile::SourceFound(qty) | map::Tile::CristalFound(qty)
-                                if qty == 0 =>
-                            {
-                                self.map_discovered.insert((x, y), map::Tile::Explored);
-                            }
-                            _ => {}
+                if robot.state == RobotState::Charging {
+                    if !robot::wait_on_terrain(robot) {
+                        let previous_position = robot.position;
+                        if !robot::deliver_cargo(
+                            robot,
+                            &mut self.docking,
+                            &self.map_discovered,
+                            &self.home_field,
+                            None,
+                            &self.tx_base,
+                        ) {
+                            robot::return_to_charge(robot, &self.map_discovered, &self.home_field);
                         }
+                        robot::apply_terrain_cost(robot, previous_position, &self.map);
                     }
-                    if let Some(new_target) = robot::find_nearest_resource(
+                    continue;
+                }
+
+                // Loin de la base, la cargaison est laissée aux transporteurs
+                if has_haulers {
+                    roles::drop_cargo(robot, &mut self.drops);
+                }
+
+                // Une soute entamée est d'abord rapportée avant de viser un autre gisement
+                if robot.target_resource.is_none()
+                    && robot.collected_resources == 0
+                    && let Some(new_target) = robot::find_nearest_resource(
                         robot,
                         &self.map_discovered,
                         &reserved_positions,
-                    ) {
-                        robot.target_resource = Some(new_target);
-                        reserved_positions.insert((new_target.0, new_target.1));
-                    }
+                        &self.home_field,
+                    )
+                {
+                    robot.target_resource = Some(new_target);
+                    reserved_positions.insert((new_target.0, new_target.1));
+                }
+                if robot.target_resource.is_none() && robot.collected_resources == 0 {
+                    lifecycle::enter(robot, RobotState::Idle);
                 }
 
-                if let Some(_target) = robot.target_resource {
+                if (robot.target_resource.is_some() || robot.collected_resources > 0)
+                    && !robot::wait_on_terrain(robot)
+                {
                     let tx_base = self.tx_base.clone();
                     let before = robot.target_resource;
+                    let previous_position = robot.position;
+                    let traffic = (self.planner == planning::Planner::Cooperative)
+                        .then_some(&mut self.reservations);
                     robot::collect_resources(
                         robot,
                         &mut self.map,
-                        self.width,
-                        self.height,
+                        &self.map_discovered,
+                        &self.home_field,
+                        &mut self.docking,
+                        traffic,
                         &tx_base,
-                        &reserved_positions,
                     );
+                    robot::apply_terrain_cost(robot, previous_position, &self.map);
 
                     if let Some(target) = before
-                        && matches!(
-                            self.map[target.1 as usize][target.0 as usize],
-                            map::Tile::Explored
-                        )
+                        && self.map[target.1 as usize][target.0 as usize]
+                            .deposit_left()
+                            .is_none()
+                        && let Some(known) =
+                            Arc::make_mut(&mut self.map_discovered).get_mut(&(target.0, target.1))
                     {
-                        self.map_discovered
-                            .insert((target.0, target.1), map::Tile::Explored);
+                        known.deposit = None;
                     }
                 }
             }
         }
 
-        // Redessiner la base
-        let base_center = (self.width / 2, self.height / 2);
-        for dy in -1..=1 {
-            for dx in -1..=1 {
-                let bx = (base_center.0 as i16 + dx) as usize;
-                let by = (base_center.1 as i16 + dy) as usize;
-                self.map[by][bx] = map::Tile::Base;
+        self.repair();
+        if self.planner == planning::Planner::Cooperative {
+            for robot in &mut self.robots {
+                if robot.robot_type == robot::RobotType::Collecteur {
+                    cooperative::maintain(robot, &self.map_discovered, &mut self.reservations);
+                }
+            }
+        }
+        if !dug.is_empty() {
+            self.detect_unreachable_deposits();
+        }
+
+        if let Some(dynamics) = &mut self.dynamics {
+            dynamics.step(
+                self.tick,
+                &mut self.map,
+                (self.base_center.0, self.base_center.1),
+            );
+        }
+
+        let changed = match &mut self.events {
+            Some(events) => {
+                let occupied: HashSet<(u16, u16)> = self
+                    .robots
+                    .iter()
+                    .map(|r| (r.position.0, r.position.1))
+                    .collect();
+                events.step(
+                    self.tick,
+                    &mut self.map,
+                    (self.base_center.0, self.base_center.1),
+                    &occupied,
+                )
+            }
+            None => Vec::new(),
+        };
+        if !changed.is_empty() {
+            self.detect_unreachable_deposits();
+        }
+
+        self.update_home_field();
+
+        if let Some(reliability) = &mut self.reliability {
+            self.breakdowns +=
+                reliability.step(&mut self.robots, &previous_positions, &mut self.drops);
+        }
+
+        for robot in &mut self.robots {
+            if let Some(&previous) = previous_positions.get(&robot.id) {
+                roles::track_idle(robot, previous);
+                robot::update_energy(robot, previous, &self.home_field);
+            }
+            if robot::at_base(robot) && robot.upgrades != self.upgrades {
+                robot.upgrades = self.upgrades;
+                tracing::info!(
+                    "Robot {}: améliorations installées ({})",
+                    robot.id,
+                    robot.upgrades.short()
+                );
+            }
+        }
+
+        self.handle_stalled();
+        self.stats.transitions += self
+            .robots
+            .iter()
+            .filter(|r| previous_states.get(&r.id).is_some_and(|&s| s != r.state))
+            .count() as u64;
+
+        // Collecteurs réunis sur une même case hors de la base, pour comparer les planificateurs
+        let mut occupancy: HashMap<(u16, u16), u32> = HashMap::new();
+        for robot in &self.robots {
+            if robot.robot_type == robot::RobotType::Collecteur {
+                *occupancy
+                    .entry((robot.position.0, robot.position.1))
+                    .or_default() += 1;
             }
         }
+        self.conflicts += occupancy
+            .iter()
+            .filter(|&(&(x, y), &count)| count > 1 && !self.map[y as usize][x as usize].is_base())
+            .count() as u32;
+
+        // Historique des passages pour la carte de chaleur
+        for robot in &self.robots {
+            self.visit_ticks
+                .insert((robot.position.0, robot.position.1), self.tick);
+        }
+
+        self.stats
+            .record(&self.totals, &self.map, &self.map_discovered, &self.robots);
     }
 }
@@ -0,0 +1,388 @@
+use crate::SimulationError;
+use crate::config::SpecParams;
+use crate::map::Terrain;
+use noise::{Fbm, MultiFractal, NoiseFn, Perlin};
+use rand::{Rng, SeedableRng, rngs::StdRng};
+
+/// Génère le terrain (murs et sol) d'une carte.
+pub trait MapGenerator {
+    fn name(&self) -> &'static str;
+    fn generate(&self, width: u16, height: u16) -> Result<Vec<Vec<Terrain>>, SimulationError>;
+}
+
+/// Plus grand côté de salle accepté par `from_spec` : la salle et ses murs (`max_size + 2`)
+/// doivent tenir dans un `u16`.
+const MAX_ROOM_SIZE: u16 = u16::MAX - 3;
+
+/// Noms acceptés par `from_spec`.
+pub const GENERATOR_NAMES: [&str; 5] = ["perlin", "fractal", "caves", "rooms", "plains"];
+
+/// Construit un générateur depuis `nom[:clé=valeur,...]`, ex. `caves:fill=0.48,iterations=4`.
+pub fn from_spec(spec: &str) -> Result<Box<dyn MapGenerator>, String> {
+    let (name, mut params) = SpecParams::parse(spec)?;
+    let generator: Box<dyn MapGenerator> = match name {
+        "perlin" => Box::new(PerlinThreshold {
+            seed: params.take("seed", 21)?,
+            scale: params.take("scale", 0.1)?,
+            threshold: params.take("threshold", 0.3)?,
+        }),
+        "fractal" => Box::new(FractalNoise {
+            seed: params.take("seed", 21)?,
+            scale: params.take("scale", 0.05)?,
+            octaves: params.take("octaves", 4)?,
+            threshold: params.take("threshold", 0.2)?,
+        }),
+        "caves" => Box::new(CellularCaves {
+            seed: params.take("seed", 21)?,
+            fill: take_ratio(&mut params, "fill", 0.45)?,
+            iterations: params.take("iterations", 5)?,
+        }),
+        "rooms" => {
+            let seed = params.take("seed", 21)?;
+            let rooms = params.take("rooms", 12)?;
+            let min_size = params.take("min_size", 4)?;
+            let max_size = params.take("max_size", 12)?;
+            if min_size.max(max_size) > MAX_ROOM_SIZE {
+                return Err(format!(
+                    "min_size et max_size doivent valoir au plus {MAX_ROOM_SIZE}: {}",
+                    min_size.max(max_size)
+                ));
+            }
+            Box::new(RoomsAndCorridors {
+                seed,
+                rooms,
+                min_size,
+                max_size,
+            })
+        }
+        "plains" => Box::new(OpenPlains {
+            seed: params.take("seed", 21)?,
+            density: take_ratio(&mut params, "density", 0.04)?,
+        }),
+        other => {
+            return Err(format!(
+                "générateur inconnu: {other} (disponibles: {})",
+                GENERATOR_NAMES.join(", ")
+            ));
+        }
+    };
+    params.finish(name)?;
+    Ok(generator)
+}
+
+/// Proportion entre 0 et 1 ; une valeur hors bornes ou `NaN` est refusée.
+fn take_ratio(params: &mut SpecParams, key: &str, default: f64) -> Result<f64, String> {
+    let value: f64 = params.take(key, default)?;
+    if !(0.0..=1.0).contains(&value) {
+        return Err(format!("{key} doit être entre 0 et 1: {value}"));
+    }
+    Ok(value)
+}
+
+fn filled(width: u16, height: u16, terrain: Terrain) -> Vec<Vec<Terrain>> {
+    vec![vec![terrain; width as usize]; height as usize]
+}
+
+/// Seuil sur un bruit de Perlin simple (générateur historique).
+pub struct PerlinThreshold {
+    pub seed: u32,
+    pub scale: f64,
+    pub threshold: f64,
+}
+
+impl MapGenerator for PerlinThreshold {
+    fn name(&self) -> &'static str {
+        "perlin"
+    }
+
+    fn generate(&self, width: u16, height: u16) -> Result<Vec<Vec<Terrain>>, SimulationError> {
+        let perlin = Perlin::new(self.seed);
+        let map = (0..height)
+            .map(|y| {
+                (0..width)
+                    .map(|x| {
+                        let noise_val =
+                            perlin.get([x as f64 * self.scale, y as f64 * self.scale, 0.0]);
+                        if noise_val < self.threshold {
+                            Terrain::Floor
+                        } else {
+                            Terrain::Wall
+                        }
+                    })
+                    .collect::<Vec<Terrain>>()
+            })
+            .collect::<Vec<Vec<Terrain>>>();
+        Ok(map)
+    }
+}
+
+/// Seuil sur un bruit fractal (fBm) multi-octaves : reliefs plus détaillés.
+pub struct FractalNoise {
+    pub seed: u32,
+    pub scale: f64,
+    pub octaves: usize,
+    pub threshold: f64,
+}
+
+impl MapGenerator for FractalNoise {
+    fn name(&self) -> &'static str {
+        "fractal"
+    }
+
+    fn generate(&self, width: u16, height: u16) -> Result<Vec<Vec<Terrain>>, SimulationError> {
+        // Chaque octave prend la graine suivante (`seed + i`, sans débordement permis) : la
+        // graine est ramenée sous `u32::MAX - MAX_OCTAVES`
+        let seed = self.seed % (u32::MAX - Fbm::<Perlin>::MAX_OCTAVES as u32);
+        let fbm = Fbm::<Perlin>::new(seed).set_octaves(self.octaves);
+        let map = (0..height)
+            .map(|y| {
+                (0..width)
+                    .map(|x| {
+                        let noise_val =
+                            fbm.get([x as f64 * self.scale, y as f64 * self.scale, 0.0]);
+                        if noise_val < self.threshold {
+                            Terrain::Floor
+                        } else {
+                            Terrain::Wall
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+        Ok(map)
+    }
+}
+
+/// Grottes par automate cellulaire : remplissage aléatoire puis lissage (règle 4-5).
+pub struct CellularCaves {
+    pub seed: u64,
+    pub fill: f64,
+    pub iterations: usize,
+}
+
+impl CellularCaves {
+    /// Murs parmi les 8 voisins, le hors-carte compte comme mur.
+    fn wall_neighbours(map: &[Vec<Terrain>], x: usize, y: usize) -> usize {
+        let mut walls = 0;
+        for dy in -1i32..=1 {
+            for dx in -1i32..=1 {
+                if dx == 0 && dy == 0 {
+                    continue;
+                }
+                let nx = x as i32 + dx;
+                let ny = y as i32 + dy;
+                let is_wall = ny < 0
+                    || nx < 0
+                    || ny as usize >= map.len()
+                    || nx as usize >= map[0].len()
+                    || map[ny as usize][nx as usize] == Terrain::Wall;
+                if is_wall {
+                    walls += 1;
+                }
+            }
+        }
+        walls
+    }
+}
+
+impl MapGenerator for CellularCaves {
+    fn name(&self) -> &'static str {
+        "caves"
+    }
+
+    fn generate(&self, width: u16, height: u16) -> Result<Vec<Vec<Terrain>>, SimulationError> {
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let mut map: Vec<Vec<Terrain>> = (0..height)
+            .map(|_| {
+                (0..width)
+                    .map(|_| {
+                        if rng.gen_bool(self.fill) {
+                            Terrain::Wall
+                        } else {
+                            Terrain::Floor
+                        }
+                    })
+                    .collect()
+            })
+            .collect();
+
+        for _ in 0..self.iterations {
+            let previous = map.clone();
+            for (y, row) in map.iter_mut().enumerate() {
+                for (x, tile) in row.iter_mut().enumerate() {
+                    match Self::wall_neighbours(&previous, x, y) {
+                        5.. => *tile = Terrain::Wall,
+                        0..=3 => *tile = Terrain::Floor,
+                        _ => {}
+                    }
+                }
+            }
+        }
+        Ok(map)
+    }
+}
+
+/// Salles rectangulaires reliées par des couloirs en L.
+pub struct RoomsAndCorridors {
+    pub seed: u64,
+    pub rooms: usize,
+    pub min_size: u16,
+    pub max_size: u16,
+}
+
+impl MapGenerator for RoomsAndCorridors {
+    fn name(&self) -> &'static str {
+        "rooms"
+    }
+
+    fn generate(&self, width: u16, height: u16) -> Result<Vec<Vec<Terrain>>, SimulationError> {
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let mut map = filled(width, height, Terrain::Wall);
+        let min_size = self.min_size.max(1);
+        let max_size = self.max_size.max(min_size);
+        if width <= max_size + 2 || height <= max_size + 2 {
+            return Ok(filled(width, height, Terrain::Floor));
+        }
+
+        let mut centers: Vec<(u16, u16)> = Vec::new();
+        let mut rooms: Vec<(u16, u16, u16, u16)> = Vec::new();
+        for _ in 0..self.rooms * 10 {
+            if rooms.len() == self.rooms {
+                break;
+            }
+            let w = rng.gen_range(min_size..=max_size);
+            let h = rng.gen_range(min_size..=max_size);
+            let x = rng.gen_range(1..width - w - 1);
+            let y = rng.gen_range(1..height - h - 1);
+            let overlaps = rooms.iter().any(|&(rx, ry, rw, rh)| {
+                x <= rx + rw && rx <= x + w && y <= ry + rh && ry <= y + h
+            });
+            if overlaps {
+                continue;
+            }
+            for row in map.iter_mut().skip(y as usize).take(h as usize) {
+                for tile in row.iter_mut().skip(x as usize).take(w as usize) {
+                    *tile = Terrain::Floor;
+                }
+            }
+            rooms.push((x, y, w, h));
+            centers.push((x + w / 2, y + h / 2));
+        }
+
+        for pair in centers.windows(2) {
+            let ((x1, y1), (x2, y2)) = (pair[0], pair[1]);
+            for x in x1.min(x2)..=x1.max(x2) {
+                map[y1 as usize][x as usize] = Terrain::Floor;
+            }
+            for y in y1.min(y2)..=y1.max(y2) {
+                map[y as usize][x2 as usize] = Terrain::Floor;
+            }
+        }
+        Ok(map)
+    }
+}
+
+/// Plaine ouverte parsemée de petits obstacles.
+pub struct OpenPlains {
+    pub seed: u64,
+    pub density: f64,
+}
+
+impl MapGenerator for OpenPlains {
+    fn name(&self) -> &'static str {
+        "plains"
+    }
+
+    fn generate(&self, width: u16, height: u16) -> Result<Vec<Vec<Terrain>>, SimulationError> {
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let mut map = filled(width, height, Terrain::Floor);
+        for y in 0..height {
+            for x in 0..width {
+                if !rng.gen_bool(self.density) {
+                    continue;
+                }
+                // Obstacle de 1×1 à 2×2 cases
+                let size = rng.gen_range(1..=2);
+                for oy in y..(y + size).min(height) {
+                    for ox in x..(x + size).min(width) {
+                        map[oy as usize][ox as usize] = Terrain::Wall;
+                    }
+                }
+            }
+        }
+        Ok(map)
+    }
+}
+
+/// Plaques de terrain (sable, eau, éboulis) posées sur le sol d'une carte générée.
+pub struct TerrainMix {
+    pub seed: u32,
+    pub scale: f64,
+    /// Part approximative du sol recouverte par chaque terrain.
+    pub sand: f64,
+    pub water: f64,
+    pub rubble: f64,
+}
+
+impl TerrainMix {
+    /// `none` ou `mixed[:sand=0.15,water=0.05,rubble=0.04,seed=5,scale=0.12]`.
+    pub fn from_spec(spec: &str) -> Result<Option<Self>, String> {
+        let (name, mut params) = SpecParams::parse(spec)?;
+        let mix = match name {
+            "none" => None,
+            "mixed" => Some(TerrainMix {
+                seed: params.take("seed", 5)?,
+                scale: params.take("scale", 0.12)?,
+                sand: params.take("sand", 0.15)?,
+                water: params.take("water", 0.05)?,
+                rubble: params.take("rubble", 0.04)?,
+            }),
+            other => {
+                return Err(format!(
+                    "terrain inconnu: {other} (disponibles: none, mixed)"
+                ));
+            }
+        };
+        params.finish(name)?;
+        Ok(mix)
+    }
+
+    /// Chaque terrain a son propre bruit ; les cases de sol au-dessus du quantile
+    /// `1 - part` deviennent ce terrain, ce qui forme des plaques.
+    pub fn apply(&self, map: &mut [Vec<Terrain>]) {
+        let layers = [
+            (Terrain::Sand, self.sand, 0.0),
+            (Terrain::Water, self.water, 10.0),
+            (Terrain::Rubble, self.rubble, 20.0),
+        ];
+        let perlin = Perlin::new(self.seed);
+        for (terrain, share, offset) in layers {
+            let floor: Vec<(usize, usize, f64)> = map
+                .iter()
+                .enumerate()
+                .flat_map(|(y, row)| {
+                    row.iter()
+                        .enumerate()
+                        .filter(|(_, t)| **t == Terrain::Floor)
+                        .map(move |(x, _)| (x, y))
+                })
+                .map(|(x, y)| {
+                    let value = perlin.get([x as f64 * self.scale, y as f64 * self.scale, offset]);
+                    (x, y, value)
+                })
+                .collect();
+            let mut values: Vec<f64> = floor.iter().map(|&(_, _, v)| v).collect();
+            values.sort_by(f64::total_cmp);
+            let covered = (values.len() as f64 * share.clamp(0.0, 1.0)) as usize;
+            if covered == 0 {
+                continue;
+            }
+            let threshold = values[values.len() - covered];
+            for (x, y, value) in floor {
+                if value >= threshold {
+                    map[y][x] = terrain;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,78 @@
+use crate::robot::Robot;
+
+/// État d'un robot dans son cycle de vie : il décide de ce que le robot fait à chaque tick.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum RobotState {
+    /// Sans tâche.
+    #[default]
+    Idle,
+    /// Éclaireur à la recherche de cases inconnues.
+    Exploring,
+    /// Éclaireur rapportant un gisement repéré.
+    ReturningWithReport,
+    /// En route vers sa cible : gisement, cargaison au sol, chantier ou robot à dépanner.
+    GoingToDeposit,
+    /// Collecteur sur son gisement.
+    Mining,
+    /// Cargaison (ou robot remorqué) ramenée à la base.
+    ReturningWithCargo,
+    /// Retour à la base pour recharger, ou en charge.
+    Charging,
+    /// En dégagement, en panne ou remorqué : le robot ne joue plus son rôle.
+    Stuck,
+}
+
+impl RobotState {
+    pub const ALL: [RobotState; 8] = [
+        RobotState::Idle,
+        RobotState::Exploring,
+        RobotState::ReturningWithReport,
+        RobotState::GoingToDeposit,
+        RobotState::Mining,
+        RobotState::ReturningWithCargo,
+        RobotState::Charging,
+        RobotState::Stuck,
+    ];
+
+    /// Libellé court du tableau de bord.
+    pub fn label(self) -> &'static str {
+        match self {
+            RobotState::Idle => "Ina",
+            RobotState::Exploring => "Exp",
+            RobotState::ReturningWithReport => "Rap",
+            RobotState::GoingToDeposit => "Ver",
+            RobotState::Mining => "Min",
+            RobotState::ReturningWithCargo => "Car",
+            RobotState::Charging => "Chg",
+            RobotState::Stuck => "Blq",
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            RobotState::Idle => "inactif",
+            RobotState::Exploring => "exploration",
+            RobotState::ReturningWithReport => "retour avec rapport",
+            RobotState::GoingToDeposit => "vers sa cible",
+            RobotState::Mining => "minage",
+            RobotState::ReturningWithCargo => "retour chargé",
+            RobotState::Charging => "recharge",
+            RobotState::Stuck => "bloqué",
+        }
+    }
+}
+
+/// Fait passer le robot dans `state` au moment où son comportement change, et journalise
+/// la transition.
+pub fn enter(robot: &mut Robot, state: RobotState) {
+    if state == robot.state {
+        return;
+    }
+    tracing::info!(
+        "Robot {}: {} -> {}",
+        robot.id,
+        robot.state.name(),
+        state.name()
+    );
+    robot.state = state;
+}
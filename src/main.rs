@@ -1,27 +1,46 @@
 use crate::game_state::GameState;
 use rand::{SeedableRng, rngs::StdRng};
 use ratatui::{
-    DefaultTerminal, Frame,
-    crossterm::event::{self, Event, KeyCode},
-    layout::Size,
-    prelude::*,
-    style::{Color, Style},
-    text::{Line, Span},
-    widgets::Paragraph,
+    DefaultTerminal,
+    crossterm::{
+        event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode},
+        execute,
+    },
 };
 use std::fmt::Display;
 use std::time::{Duration, Instant};
 use tokio::sync::{broadcast, mpsc};
 
 mod base;
+mod config;
+mod cooperative;
+mod docking;
+mod dynamics;
+mod events;
+mod failures;
+mod flow_field;
 mod game_state;
+mod generator;
+mod lifecycle;
 mod map;
+mod map_file;
+mod movement;
+mod placement;
+mod planning;
+mod recovery;
+mod resources;
 mod robot;
+mod roles;
+mod stats;
+mod ui;
+mod upgrades;
 mod utils;
 
 #[derive(Debug, thiserror::Error)]
 pub enum SimulationError {
     Io(#[from] std::io::Error),
+    InvalidArgument(String),
+    InvalidMap(String),
 }
 
 impl Display for SimulationError {
@@ -36,6 +55,7 @@ pub type Result<T> = std::result::Result<T, SimulationError>;
 async fn main() -> Result<()> {
     let _guard = utils::configure_logger();
     tracing::info!("Application started!");
+    let config = config::Config::from_args()?;
 
     // rng et channels setup
     const REPEATED_SEED: [u8; 32] = [0; 32];
@@ -50,72 +70,177 @@ async fn main() -> Result<()> {
         base_clone.run(rx_base).await;
     });
 
+    // carte écrite à la main, chargée avant le terminal pour afficher les erreurs
+    let loaded = config.map.as_deref().map(map_file::load).transpose()?;
+
     // terminal setup
     let terminal = ratatui::init();
-    let area: Size = terminal.size().map_err(SimulationError::Io)?;
-
-    // map generation
-    let mut map = map::generate_map(area.width, area.height - 1)?;
-    let sources = map::generate_sources_rand(area.width, area.height - 1)?;
-    sources.iter().for_each(|(x, y, resource)| {
-        if let map::Tile::Floor = map[*y as usize][*x as usize] {
-            map[*y as usize][*x as usize] = resource.clone();
-        }
-    });
+    execute!(std::io::stdout(), EnableMouseCapture).map_err(SimulationError::Io)?;
+    let area = terminal.size().map_err(SimulationError::Io)?;
 
-    // base center generation
-    let start_x = (area.width / 2) - 1;
-    let start_y = (area.height / 2) - 1;
-    for y in start_y..start_y + 3 {
-        for x in start_x..start_x + 3 {
-            map[y as usize][x as usize] = map::Tile::Base;
-        }
-    }
+    let world = match loaded {
+        Some(world) => world,
+        None => generate_world(
+            config.width.unwrap_or(area.width),
+            config.height.unwrap_or(area.height.saturating_sub(1)),
+            &config,
+        )?,
+    };
 
-    // robots generation -- A REFACTO
-    let robot1 = robot::robots_eclaireur(area.width, area.height, (1, 0));
-    let robot2 = robot::robots_eclaireur(area.width, area.height, (0, 1));
-    let robot3 = robot::robots_collecteur(area.width, area.height);
-    let robot4 = robot::robots_collecteur(area.width, area.height);
+    // robots generation
+    let home = world.base_center;
+    let mut robots = if world.spawns.is_empty() {
+        vec![
+            robot::robots_eclaireur(home, (1, 0)),
+            robot::robots_eclaireur(home, (0, 1)),
+            robot::robots_collecteur(home),
+            robot::robots_collecteur(home),
+        ]
+    } else {
+        const DIRECTIONS: [(i16, i16); 4] = [(1, 0), (0, 1), (-1, 0), (0, -1)];
+        world
+            .spawns
+            .iter()
+            .enumerate()
+            .map(|(i, (position, robot_type))| {
+                let mut robot = match robot_type {
+                    robot::RobotType::Eclaireur => {
+                        robot::robots_eclaireur(home, DIRECTIONS[i % DIRECTIONS.len()])
+                    }
+                    other => robot::robots_role(*other, home),
+                };
+                robot.position = *position;
+                robot
+            })
+            .collect()
+    };
+    // Les robots de soutien déjà placés par la carte comptent dans le nombre demandé
+    for (robot_type, count) in [
+        (robot::RobotType::Transporteur, config.haulers),
+        (robot::RobotType::Batisseur, config.builders),
+        (robot::RobotType::Reparateur, config.repairers),
+    ] {
+        let placed = robots.iter().filter(|r| r.robot_type == robot_type).count();
+        robots.extend((placed..count).map(|_| robot::robots_role(robot_type, home)));
+    }
+    robot::upgrade_tools(&mut robots, config.upgraded_collectors);
 
-    // game configuration -- A REFACTO
+    // game configuration
     tracing::info!("Map generated");
     let mut game_state = GameState::new(
-        map,
-        area.width,
-        area.height,
-        vec![robot1, robot2, robot3, robot4],
+        world.map,
+        world.width,
+        world.height,
+        robots,
+        home,
         base,
         rx_broadcast,
         tx_base.clone(),
     );
 
+    game_state.detect_unreachable_deposits();
+    game_state.set_movement(config.movement);
+    game_state.planner = config.planner;
+    game_state.docking = docking::Docking::new(home, config.docks, config.unload_ticks);
+    game_state.dynamics = dynamics::ResourceDynamics::from_spec(&config.dynamics)
+        .map_err(SimulationError::InvalidArgument)?;
+    if let Some(dynamics) = &mut game_state.dynamics {
+        dynamics.track_sources(&game_state.map);
+    }
+    game_state.events =
+        events::WorldEvents::from_spec(&config.events).map_err(SimulationError::InvalidArgument)?;
+    if let Some(events) = &game_state.events {
+        tracing::info!("Événements du monde (graine {})", events.seed);
+    }
+    game_state.reliability = failures::Reliability::from_spec(&config.failures)
+        .map_err(SimulationError::InvalidArgument)?;
+    if let Some(reliability) = &game_state.reliability {
+        tracing::info!("Pannes des robots (graine {})", reliability.seed);
+    }
+    game_state.random_walk = recovery::RandomWalk::from_spec(&config.recovery)
+        .map_err(SimulationError::InvalidArgument)?;
+    tracing::info!(
+        "Dégagement des robots (graine {})",
+        game_state.random_walk.seed
+    );
     tracing::info!("Game state initialized");
 
-    let res = run(terminal, &mut game_state, area);
+    let res = run(terminal, &mut game_state, &config);
     tracing::info!("Game loop exited");
+    let _ = execute!(std::io::stdout(), DisableMouseCapture);
     ratatui::restore();
+    if game_state.is_finished() {
+        println!("{}", game_state.summary());
+    }
     res
 }
 
-fn run(mut terminal: DefaultTerminal, game_state: &mut GameState, area: Size) -> Result<()> {
+/// Carte procédurale : terrain du générateur choisi, base 3×3 au centre et gisements sur le sol atteignable.
+fn generate_world(width: u16, height: u16, config: &config::Config) -> Result<map_file::MapFile> {
+    // Le terminal peut être plus petit que la base
+    if width < config::MIN_WORLD_SIZE || height < config::MIN_WORLD_SIZE {
+        return Err(SimulationError::InvalidArgument(format!(
+            "monde de {width}×{height} trop petit pour la base ({0}×{0})",
+            config::MIN_WORLD_SIZE
+        )));
+    }
+    let base_center = (width / 2, height / 2);
+    let generator =
+        generator::from_spec(&config.generator).map_err(SimulationError::InvalidArgument)?;
+    tracing::info!("Générateur de terrain: {}", generator.name());
+    let mut terrain = generator.generate(width, height)?;
+    if let Some(mix) = generator::TerrainMix::from_spec(&config.terrain)
+        .map_err(SimulationError::InvalidArgument)?
+    {
+        mix.apply(&mut terrain);
+    }
+    let mut map = map::from_terrain(terrain);
+    map::clear_base_area(&mut map, base_center);
+    map::ensure_connectivity(&mut map, base_center, config.connectivity);
+
+    // base center generation
+    let start_x = (width / 2) - 1;
+    let start_y = (height / 2) - 1;
+    for y in start_y..start_y + 3 {
+        for x in start_x..start_x + 3 {
+            map[y as usize][x as usize] = map::Cell::base();
+        }
+    }
+
+    let placement =
+        placement::from_spec(&config.resources).map_err(SimulationError::InvalidArgument)?;
+    placement.apply(&mut map, base_center);
+
+    Ok(map_file::MapFile {
+        map,
+        width,
+        height,
+        base_center: robot::RobotPosition(base_center.0, base_center.1),
+        spawns: Vec::new(),
+    })
+}
+
+fn run(
+    mut terminal: DefaultTerminal,
+    game_state: &mut GameState,
+    config: &config::Config,
+) -> Result<()> {
     const TICK_RATE: Duration = Duration::from_millis(50);
 
+    let mut ui_state = ui::UiState::default();
     let mut last_tick = Instant::now();
     event::poll(Duration::from_millis(0)).map_err(SimulationError::Io)?;
     tracing::info!("Crossterm configured");
     loop {
-        if last_tick.elapsed() >= TICK_RATE {
+        if !ui_state.paused && last_tick.elapsed() >= TICK_RATE {
             game_state.update();
         }
 
-        while let Ok(msg) = game_state.rx_broadcast.try_recv() {
-            match msg {
-                base::BroadcastMessage::BaseStats { energy, crystals } => {
-                    game_state.energy = energy;
-                    game_state.crystals = crystals;
-                }
-            }
+        game_state.handle_broadcasts();
+
+        if config.stop_when_done && game_state.is_finished() {
+            tracing::info!("{}", game_state.summary());
+            return Ok(());
         }
 
         last_tick = Instant::now();
@@ -123,91 +248,51 @@ fn run(mut terminal: DefaultTerminal, game_state: &mut GameState, area: Size) ->
         let timeout = TICK_RATE
             .checked_sub(last_tick.elapsed())
             .unwrap_or(Duration::from_millis(0));
-        if event::poll(timeout).map_err(SimulationError::Io)?
-            && let Event::Key(key_event) = event::read().map_err(SimulationError::Io)?
-            && key_event.code == KeyCode::Char(' ')
-        {
-            tracing::info!("Space key pressed, exiting game loop");
-            return Ok(());
+        if event::poll(timeout).map_err(SimulationError::Io)? {
+            match event::read().map_err(SimulationError::Io)? {
+                Event::Key(key_event) => match key_event.code {
+                    KeyCode::Char(' ') => {
+                        tracing::info!("Space key pressed, exiting game loop");
+                        return Ok(());
+                    }
+                    KeyCode::Char('v') => ui_state.next_view(),
+                    KeyCode::Char('d') => ui_state.toggle_dashboard(),
+                    KeyCode::Char('p') => ui_state.toggle_pause(),
+                    KeyCode::Char('s') => {
+                        let path = config.save_path();
+                        if let Err(err) = map_file::save(&path, &game_state.map, &game_state.robots)
+                        {
+                            tracing::error!("Sauvegarde impossible: {}", err);
+                        }
+                    }
+                    KeyCode::Char('w') => ui_state.select_tool(ui::EditTool::Wall),
+                    KeyCode::Char('r') => ui_state.select_tool(ui::EditTool::MoveRobot),
+                    KeyCode::Tab => ui_state.next_robot(game_state.robots.len()),
+                    KeyCode::Left => ui_state.scroll(-1, 0),
+                    KeyCode::Right => ui_state.scroll(1, 0),
+                    KeyCode::Up => ui_state.scroll(0, -1),
+                    KeyCode::Down => ui_state.scroll(0, 1),
+                    // Lettre d'une ressource du registre : outil de pose de gisement
+                    KeyCode::Char(ch) => {
+                        if let Some(resource) =
+                            resources::Resource::from_glyph(ch.to_ascii_uppercase())
+                        {
+                            ui_state.select_tool(ui::EditTool::Deposit(resource));
+                        }
+                    }
+                    _ => {}
+                },
+                Event::Mouse(mouse_event) => ui_state.handle_mouse(game_state, mouse_event),
+                Event::Resize(width, height) => {
+                    tracing::info!("Terminal resized to {}x{}", width, height);
+                    terminal.autoresize().map_err(SimulationError::Io)?;
+                }
+                _ => {}
+            }
         }
 
         terminal
-            .draw(|f| render_map_simple(f, game_state, area))
+            .draw(|f| ui::render(f, game_state, &mut ui_state))
             .map_err(SimulationError::Io)?;
     }
 }
-
-fn render_map_simple(f: &mut Frame<'_>, game_state: &GameState, area: Size) {
-    let score_text = vec![Line::from(vec![
-        Span::styled("Énergie: ", Style::default().fg(Color::Green)),
-        Span::styled(
-            game_state.energy.to_string(),
-            Style::default().fg(Color::White),
-        ),
-        Span::raw("   "),
-        Span::styled("Cristaux: ", Style::default().fg(Color::Magenta)),
-        Span::styled(
-            game_state.crystals.to_string(),
-            Style::default().fg(Color::White),
-        ),
-    ])];
-    let score_widget = Paragraph::new(score_text);
-    f.render_widget(score_widget, Rect::new(0, 0, area.width, 1));
-
-    let map_lines: Vec<Line> = game_state
-        .map
-        .iter()
-        .enumerate()
-        .take((game_state.height.saturating_sub(1)) as usize)
-        .map(|(y, row)| {
-            let spans: Vec<Span> = row
-                .iter()
-                .enumerate()
-                .take(game_state.width as usize)
-                .map(|(x, tile)| {
-                    let robot_here = game_state
-                        .robots
-                        .iter()
-                        .find(|r| r.position.0 == x as u16 && r.position.1 == y as u16);
-
-                    let (ch, color) = if let Some(robot) = robot_here {
-                        match robot.robot_type {
-                            robot::RobotType::Eclaireur => ('X', Color::Red),
-                            robot::RobotType::Collecteur => ('O', Color::Magenta),
-                        }
-                    } else {
-                        match tile {
-                            map::Tile::Wall => ('0', Color::LightCyan),
-                            map::Tile::Floor => (' ', Color::Reset),
-                            map::Tile::Source(_qty) => ('E', Color::Green),
-                            map::Tile::SourceFound(qty) => {
-                                if *qty > 0 {
-                                    ('E', Color::Blue)
-                                } else {
-                                    ('░', Color::Gray)
-                                }
-                            }
-                            map::Tile::Cristal(_qty) => ('C', Color::LightMagenta),
-                            map::Tile::CristalFound(qty) => {
-                                if *qty > 0 {
-                                    ('C', Color::Yellow)
-                                } else {
-                                    ('░', Color::Gray)
-                                }
-                            }
-                            map::Tile::Base => ('#', Color::LightGreen),
-                            // map::Tile::Eclaireur => ('X', Color::Red),
-                            // map::Tile::Collecteur => ('O', Color::Magenta),
-                            map::Tile::Explored => ('░', Color::Gray),
-                        }
-                    };
-                    Span::styled(ch.to_string(), Style::default().fg(color))
-                })
-                .collect();
-            Line::from(spans)
-        })
-        .collect();
-
-    let map_widget = Paragraph::new(map_lines);
-    f.render_widget(map_widget, Rect::new(0, 1, area.width, area.height));
-}
@@ -1,61 +1,299 @@
-use crate::SimulationError;
-use noise::{NoiseFn, Perlin};
-use rand::Rng;
+use std::collections::{HashMap, HashSet, VecDeque};
 
-#[derive(Clone, PartialEq, Debug)]
-pub enum Tile {
+use crate::resources::Resource;
+use crate::robot::RobotType;
+
+/// Nature du sol d'une case.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Terrain {
     Wall,
     Floor,
-    Source(u32),
-    Cristal(u32),
-    CristalFound(u32),
-    SourceFound(u32),
+    /// Sable : lent à traverser.
+    Sand,
+    /// Eau : franchissable par les éclaireurs seulement.
+    Water,
+    /// Éboulis : très lent à traverser.
+    Rubble,
+}
+
+impl Terrain {
+    /// Coût de traversée pour un type de robot, `None` si la case lui est infranchissable.
+    pub fn move_cost(self, robot_type: &RobotType) -> Option<u16> {
+        match self {
+            Terrain::Wall => None,
+            // Éclaireurs et réparateurs passent à gué, les robots chargés ou outillés non
+            Terrain::Water => match robot_type {
+                RobotType::Eclaireur | RobotType::Reparateur => Some(3),
+                RobotType::Collecteur | RobotType::Transporteur | RobotType::Batisseur => None,
+            },
+            Terrain::Sand => Some(2),
+            Terrain::Rubble => Some(4),
+            Terrain::Floor => Some(1),
+        }
+    }
+}
+
+/// Gisement posé sur une case.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Deposit {
+    pub resource: Resource,
+    pub qty: u32,
+    /// Ticks de minage déjà passés sur l'unité en cours, conservés si le collecteur
+    /// est interrompu : le suivant reprend là où il s'est arrêté.
+    pub progress: u32,
+}
+
+impl Deposit {
+    pub fn new(resource: Resource, qty: u32) -> Self {
+        Deposit {
+            resource,
+            qty,
+            progress: 0,
+        }
+    }
+}
+
+/// Construction occupant une case.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Structure {
     Base,
-    Explored,
-}
-
-pub fn generate_map(width: u16, height: u16) -> Result<Vec<Vec<Tile>>, SimulationError> {
-    let perlin = Perlin::new(21);
-    let scale = 0.1;
-    let map = (0..height)
-        .map(|y| {
-            (0..width)
-                .map(|x| {
-                    let noise_val = perlin.get([x as f64 * scale, y as f64 * scale, 0.0]);
-                    if noise_val < 0.3 {
-                        Tile::Floor
-                    } else {
-                        Tile::Wall
-                    }
-                })
-                .collect::<Vec<Tile>>()
+}
+
+/// Surcoût de traversée d'une case dans une zone dangereuse.
+pub const HAZARD_COST: u16 = 3;
+
+/// Case du monde en couches : terrain, gisement, construction et danger temporaire.
+///
+/// L'état de découverte n'en fait pas partie : l'essaim et chaque robot gardent
+/// leur copie des cases connues dans `map_discovered`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Cell {
+    pub terrain: Terrain,
+    pub deposit: Option<Deposit>,
+    pub structure: Option<Structure>,
+    /// Zone dangereuse active (événement du monde) : ralentit les robots.
+    pub hazard: bool,
+}
+
+impl Cell {
+    pub fn new(terrain: Terrain) -> Self {
+        Cell {
+            terrain,
+            deposit: None,
+            structure: None,
+            hazard: false,
+        }
+    }
+
+    /// Sol portant un gisement.
+    pub fn with_deposit(resource: Resource, qty: u32) -> Self {
+        Cell {
+            deposit: Some(Deposit::new(resource, qty)),
+            ..Cell::new(Terrain::Floor)
+        }
+    }
+
+    /// Sol occupé par la base.
+    pub fn base() -> Self {
+        Cell {
+            structure: Some(Structure::Base),
+            ..Cell::new(Terrain::Floor)
+        }
+    }
+
+    pub fn move_cost(&self, robot_type: &RobotType) -> Option<u16> {
+        let cost = self.terrain.move_cost(robot_type)?;
+        Some(if self.hazard {
+            cost + HAZARD_COST
+        } else {
+            cost
         })
-        .collect::<Vec<Vec<Tile>>>();
-    Ok(map)
-}
-
-pub fn generate_sources_rand(
-    width: u16,
-    height: u16,
-) -> Result<Vec<(u16, u16, Tile)>, SimulationError> {
-    let mut rng = rand::thread_rng();
-    let mut sources: Vec<(u16, u16, Tile)> = Vec::new();
-
-    for y in 0..height {
-        for x in 0..width {
-            let roll: f64 = rng.r#gen(); // nombre entre 0.0 et 1.0
-
-            if roll < 0.005 {
-                // 💡 0.5% de chances d’être une source d’énergie
-                let qty = rng.gen_range(5..=10);
-                sources.push((x, y, Tile::Source(qty)));
-            } else if roll < 0.008 {
-                // 💡 0.3% de chances d’être un cristal
-                let qty = rng.gen_range(5..=10);
-                sources.push((x, y, Tile::Cristal(qty)));
+    }
+
+    pub fn is_passable(&self, robot_type: &RobotType) -> bool {
+        self.move_cost(robot_type).is_some()
+    }
+
+    /// Gisement non épuisé.
+    pub fn deposit_left(&self) -> Option<Deposit> {
+        self.deposit.filter(|deposit| deposit.qty > 0)
+    }
+
+    pub fn is_base(&self) -> bool {
+        self.structure == Some(Structure::Base)
+    }
+
+    /// Sol nu, sans gisement ni construction : là où l'on peut poser un gisement.
+    pub fn is_free_floor(&self) -> bool {
+        self.terrain == Terrain::Floor && self.deposit.is_none() && self.structure.is_none()
+    }
+}
+
+/// Grille de cellules d'une grille de terrain générée.
+pub fn from_terrain(terrain: Vec<Vec<Terrain>>) -> Vec<Vec<Cell>> {
+    terrain
+        .into_iter()
+        .map(|row| row.into_iter().map(Cell::new).collect())
+        .collect()
+}
+
+/// Traitement des zones de sol non reliées à la base après génération.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Connectivity {
+    /// Creuse des couloirs pour relier chaque zone à la base.
+    #[default]
+    Carve,
+    /// Transforme les zones inaccessibles en murs.
+    Drop,
+    /// Laisse la carte telle quelle.
+    Keep,
+}
+
+impl std::str::FromStr for Connectivity {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "carve" => Ok(Connectivity::Carve),
+            "drop" => Ok(Connectivity::Drop),
+            "keep" => Ok(Connectivity::Keep),
+            other => Err(format!("mode de connectivité inconnu: {other}")),
+        }
+    }
+}
+
+/// Voisins 4-connexes dans les limites de la carte.
+pub fn neighbours(x: u16, y: u16, width: u16, height: u16) -> impl Iterator<Item = (u16, u16)> {
+    [(1i16, 0i16), (-1, 0), (0, 1), (0, -1)]
+        .into_iter()
+        .filter_map(move |(dx, dy)| {
+            let nx = x.checked_add_signed(dx)?;
+            let ny = y.checked_add_signed(dy)?;
+            (nx < width && ny < height).then_some((nx, ny))
+        })
+}
+
+/// Cases atteignables par un collecteur depuis `start` (inondation 4-connexe).
+pub fn reachable_from(map: &[Vec<Cell>], start: (u16, u16)) -> HashSet<(u16, u16)> {
+    let height = map.len() as u16;
+    let width = map.first().map_or(0, |row| row.len()) as u16;
+    let mut reached = HashSet::from([start]);
+    let mut queue = VecDeque::from([start]);
+    while let Some((x, y)) = queue.pop_front() {
+        for (nx, ny) in neighbours(x, y, width, height) {
+            if map[ny as usize][nx as usize].is_passable(&RobotType::Collecteur)
+                && reached.insert((nx, ny))
+            {
+                queue.push_back((nx, ny));
             }
         }
     }
+    reached
+}
+
+/// Dégage la base 3×3 et une bordure d'une case autour pour qu'elle soit posée sur du sol.
+pub fn clear_base_area(map: &mut [Vec<Cell>], center: (u16, u16)) {
+    let height = map.len() as i32;
+    let width = map.first().map_or(0, |row| row.len()) as i32;
+    for dy in -2..=2 {
+        for dx in -2..=2 {
+            let x = center.0 as i32 + dx;
+            let y = center.1 as i32 + dy;
+            if x >= 0 && y >= 0 && x < width && y < height {
+                map[y as usize][x as usize] = Cell::new(Terrain::Floor);
+            }
+        }
+    }
+}
+
+/// Creuse le plus court couloir entre `region` et une case de `connected`.
+fn carve_corridor(
+    map: &mut [Vec<Cell>],
+    region: &HashSet<(u16, u16)>,
+    connected: &HashSet<(u16, u16)>,
+) {
+    let height = map.len() as u16;
+    let width = map.first().map_or(0, |row| row.len()) as u16;
+    let mut came_from: HashMap<(u16, u16), (u16, u16)> = HashMap::new();
+    let mut queue: VecDeque<(u16, u16)> = region.iter().copied().collect();
+    let mut seen: HashSet<(u16, u16)> = region.clone();
 
-    Ok(sources)
+    while let Some(pos) = queue.pop_front() {
+        if connected.contains(&pos) {
+            let mut current = pos;
+            while let Some(&previous) = came_from.get(&current) {
+                let cell = &mut map[current.1 as usize][current.0 as usize];
+                if !cell.is_passable(&RobotType::Collecteur) {
+                    cell.terrain = Terrain::Floor;
+                }
+                current = previous;
+            }
+            return;
+        }
+        for next in neighbours(pos.0, pos.1, width, height) {
+            if seen.insert(next) {
+                came_from.insert(next, pos);
+                queue.push_back(next);
+            }
+        }
+    }
+}
+
+/// Garantit que le sol est relié à la base selon le mode choisi.
+pub fn ensure_connectivity(map: &mut [Vec<Cell>], base_center: (u16, u16), mode: Connectivity) {
+    if mode == Connectivity::Keep {
+        return;
+    }
+    let mut connected = reachable_from(map, base_center);
+    let open_tiles: Vec<(u16, u16)> = map
+        .iter()
+        .enumerate()
+        .flat_map(|(y, row)| {
+            row.iter()
+                .enumerate()
+                .filter(|(_, cell)| cell.is_passable(&RobotType::Collecteur))
+                .map(move |(x, _)| (x as u16, y as u16))
+        })
+        .collect();
+
+    let mut carved = 0;
+    let mut dropped = 0;
+    for pos in open_tiles {
+        if connected.contains(&pos) {
+            continue;
+        }
+        match mode {
+            Connectivity::Carve => {
+                let region = reachable_from(map, pos);
+                carve_corridor(map, &region, &connected);
+                connected = reachable_from(map, base_center);
+                carved += 1;
+            }
+            Connectivity::Drop => {
+                map[pos.1 as usize][pos.0 as usize] = Cell::new(Terrain::Wall);
+                dropped += 1;
+            }
+            Connectivity::Keep => {}
+        }
+    }
+    tracing::info!(
+        "Connectivité: {} zones reliées, {} cases isolées murées",
+        carved,
+        dropped
+    );
+}
+
+/// Gisements qu'aucun robot ne peut atteindre depuis la base.
+pub fn unreachable_deposits(map: &[Vec<Cell>], base_center: (u16, u16)) -> Vec<(u16, u16)> {
+    let reachable = reachable_from(map, base_center);
+    map.iter()
+        .enumerate()
+        .flat_map(|(y, row)| {
+            row.iter()
+                .enumerate()
+                .filter(|(_, cell)| cell.deposit_left().is_some())
+                .map(move |(x, _)| (x as u16, y as u16))
+        })
+        .filter(|pos| !reachable.contains(pos))
+        .collect()
 }
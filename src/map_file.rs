@@ -0,0 +1,316 @@
+use std::path::Path;
+
+use crate::map::{Cell, Terrain};
+use crate::resources::Resource;
+use crate::robot::{Robot, RobotPosition, RobotType};
+use crate::{Result, SimulationError};
+
+/// Carte écrite à la main, chargée à la place de la génération procédurale.
+///
+/// Format texte, une ligne par rangée, un jeton par case :
+/// - `#` mur, `.` sol, `B` base (bloc 3×3)
+/// - `:` sable, `~` eau, `%` éboulis
+/// - `E5` source d'énergie de quantité 5, `C8` cristal de quantité 8
+/// - `X` départ d'un éclaireur, `O` départ d'un collecteur (sur du sol)
+///
+/// Une ligne `; robot <lettre> <x> <y>` place un robot sur n'importe quelle case, même
+/// partagée. Les autres lignes vides ou commençant par `;` sont ignorées.
+pub struct MapFile {
+    pub map: Vec<Vec<Cell>>,
+    pub width: u16,
+    pub height: u16,
+    pub base_center: RobotPosition,
+    pub spawns: Vec<(RobotPosition, RobotType)>,
+}
+
+/// Début d'une ligne de robot, un commentaire pour les anciennes versions.
+const ROBOT_PREFIX: &str = "; robot ";
+
+fn invalid(line: usize, message: impl std::fmt::Display) -> SimulationError {
+    SimulationError::InvalidMap(format!("ligne {}: {}", line + 1, message))
+}
+
+fn parse_row(
+    line_index: usize,
+    y: u16,
+    line: &str,
+    spawns: &mut Vec<(RobotPosition, RobotType)>,
+) -> Result<Vec<Cell>> {
+    let mut row = Vec::new();
+    let mut chars = line.trim_end().chars().peekable();
+    while let Some(ch) = chars.next() {
+        let x = row.len() as u16;
+        let cell = match ch {
+            '#' => Cell::new(Terrain::Wall),
+            '.' => Cell::new(Terrain::Floor),
+            'B' => Cell::base(),
+            ':' => Cell::new(Terrain::Sand),
+            '~' => Cell::new(Terrain::Water),
+            '%' => Cell::new(Terrain::Rubble),
+            other if let Some(robot_type) = RobotType::from_glyph(other) => {
+                spawns.push((RobotPosition(x, y), robot_type));
+                Cell::new(Terrain::Floor)
+            }
+            other if let Some(resource) = Resource::from_glyph(other) => {
+                let mut digits = String::new();
+                while let Some(digit) = chars.next_if(|c| c.is_ascii_digit()) {
+                    digits.push(digit);
+                }
+                let qty = digits
+                    .parse()
+                    .map_err(|_| invalid(line_index, format!("quantité manquante après {ch}")))?;
+                Cell::with_deposit(resource, qty)
+            }
+            other => return Err(invalid(line_index, format!("symbole inconnu '{other}'"))),
+        };
+        row.push(cell);
+    }
+    Ok(row)
+}
+
+/// Centre du bloc 3×3 de cases `B`.
+fn find_base_center(map: &[Vec<Cell>]) -> Result<RobotPosition> {
+    let base_tiles: Vec<(usize, usize)> = map
+        .iter()
+        .enumerate()
+        .flat_map(|(y, row)| {
+            row.iter()
+                .enumerate()
+                .filter(|(_, cell)| cell.is_base())
+                .map(move |(x, _)| (x, y))
+        })
+        .collect();
+    let Some(&(min_x, min_y)) = base_tiles.iter().min() else {
+        return Err(SimulationError::InvalidMap(
+            "aucune case de base 'B'".into(),
+        ));
+    };
+    let is_block = base_tiles.len() == 9
+        && base_tiles
+            .iter()
+            .all(|&(x, y)| (min_x..min_x + 3).contains(&x) && (min_y..min_y + 3).contains(&y));
+    if !is_block {
+        return Err(SimulationError::InvalidMap(
+            "la base doit être un bloc 3×3 de 'B'".into(),
+        ));
+    }
+    Ok(RobotPosition(min_x as u16 + 1, min_y as u16 + 1))
+}
+
+/// Ligne `; robot <lettre> <x> <y>` : type et position du robot.
+fn parse_robot(line_index: usize, fields: &str) -> Result<(RobotPosition, RobotType)> {
+    let fields: Vec<&str> = fields.split_whitespace().collect();
+    let [glyph, x, y] = fields[..] else {
+        return Err(invalid(
+            line_index,
+            "robot attendu sous la forme '<lettre> <x> <y>'",
+        ));
+    };
+    let robot_type = glyph
+        .chars()
+        .next()
+        .filter(|_| glyph.chars().count() == 1)
+        .and_then(RobotType::from_glyph)
+        .ok_or_else(|| invalid(line_index, format!("robot inconnu '{glyph}'")))?;
+    let coordinate = |value: &str| {
+        value
+            .parse::<u16>()
+            .map_err(|_| invalid(line_index, format!("coordonnée invalide '{value}'")))
+    };
+    Ok((RobotPosition(coordinate(x)?, coordinate(y)?), robot_type))
+}
+
+pub fn load(path: &Path) -> Result<MapFile> {
+    let content = std::fs::read_to_string(path)?;
+    let mut map = Vec::new();
+    let mut spawns = Vec::new();
+    let mut placed = Vec::new();
+    for (line_index, line) in content.lines().enumerate() {
+        if let Some(fields) = line.strip_prefix(ROBOT_PREFIX) {
+            placed.push((line_index, parse_robot(line_index, fields)?));
+            continue;
+        }
+        if line.trim().is_empty() || line.starts_with(';') {
+            continue;
+        }
+        let row = parse_row(line_index, map.len() as u16, line, &mut spawns)?;
+        if let Some(first) = map.first().map(Vec::len)
+            && row.len() != first
+        {
+            return Err(invalid(
+                line_index,
+                format!("{} cases au lieu de {}", row.len(), first),
+            ));
+        }
+        map.push(row);
+    }
+    if map.is_empty() {
+        return Err(SimulationError::InvalidMap("carte vide".into()));
+    }
+
+    let (width, height) = (map[0].len() as u16, map.len() as u16);
+    for (line_index, (position, robot_type)) in placed {
+        if position.0 >= width || position.1 >= height {
+            return Err(invalid(line_index, "robot hors de la carte"));
+        }
+        spawns.push((position, robot_type));
+    }
+
+    let base_center = find_base_center(&map)?;
+    tracing::info!(
+        "Carte chargée depuis {}: {}x{}, {} robots",
+        path.display(),
+        map[0].len(),
+        map.len(),
+        spawns.len()
+    );
+    Ok(MapFile {
+        width,
+        height,
+        map,
+        base_center,
+        spawns,
+    })
+}
+
+fn cell_token(cell: &Cell) -> String {
+    if cell.is_base() {
+        return "B".into();
+    }
+    match cell.deposit_left() {
+        Some(deposit) => format!("{}{}", deposit.resource.kind().glyph, deposit.qty),
+        None => match cell.terrain {
+            Terrain::Wall => "#".into(),
+            Terrain::Floor => ".".into(),
+            Terrain::Sand => ":".into(),
+            Terrain::Water => "~".into(),
+            Terrain::Rubble => "%".into(),
+        },
+    }
+}
+
+/// Écrit le monde courant, puis chaque robot sur une ligne `; robot`, quelle que soit sa case :
+/// l'équipe rechargée est celle de la sauvegarde.
+pub fn save(path: &Path, map: &[Vec<Cell>], robots: &[Robot]) -> Result<()> {
+    let mut content = String::new();
+    for row in map {
+        for cell in row {
+            content.push_str(&cell_token(cell));
+        }
+        content.push('\n');
+    }
+    for robot in robots {
+        content.push_str(&format!(
+            "{}{} {} {}\n",
+            ROBOT_PREFIX,
+            robot.robot_type.glyph(),
+            robot.position.0,
+            robot.position.1
+        ));
+    }
+    std::fs::write(path, content)?;
+    tracing::info!("Carte sauvegardée dans {}", path.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::robot;
+
+    const MAP: &str = "\
+; carte de test
+#########
+#X..:..C8#
+#.BBB.~.#
+#.BBB%E5.#
+#.BBB..O#
+#########
+";
+
+    /// Fichier temporaire propre au test, supprimé à la fin.
+    struct TempFile(std::path::PathBuf);
+
+    impl TempFile {
+        fn new(name: &str, content: &str) -> Self {
+            let path =
+                std::env::temp_dir().join(format!("map_file_{}_{}.txt", std::process::id(), name));
+            std::fs::write(&path, content).unwrap();
+            TempFile(path)
+        }
+    }
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    #[test]
+    fn save_then_load_gives_the_same_world() {
+        let file = TempFile::new("round_trip", MAP);
+        let loaded = load(&file.0).unwrap();
+        assert_eq!((loaded.width, loaded.height), (9, 6));
+        assert_eq!(loaded.base_center, RobotPosition(3, 3));
+        assert_eq!(
+            loaded.spawns,
+            vec![
+                (RobotPosition(1, 1), RobotType::Eclaireur),
+                (RobotPosition(7, 4), RobotType::Collecteur)
+            ]
+        );
+        assert_eq!(loaded.map[1][7], Cell::with_deposit(Resource::CRYSTALS, 8));
+        assert_eq!(loaded.map[2][6].terrain, Terrain::Water);
+
+        // Des robots sur la base, sur du sable, sur un gisement et plusieurs sur une même case
+        let team = [
+            (RobotPosition(3, 3), RobotType::Eclaireur),
+            (RobotPosition(3, 3), RobotType::Collecteur),
+            (RobotPosition(2, 2), RobotType::Collecteur),
+            (RobotPosition(4, 1), RobotType::Collecteur),
+            (RobotPosition(7, 1), RobotType::Transporteur),
+            (RobotPosition(1, 1), RobotType::Batisseur),
+            (RobotPosition(1, 1), RobotType::Reparateur),
+            (RobotPosition(7, 4), RobotType::Collecteur),
+        ];
+        let robots: Vec<Robot> = team
+            .iter()
+            .map(|&(position, robot_type)| {
+                let mut robot = robot::robots_role(robot_type, loaded.base_center);
+                robot.position = position;
+                robot
+            })
+            .collect();
+        let saved = TempFile::new("round_trip_saved", "");
+        save(&saved.0, &loaded.map, &robots).unwrap();
+        let reloaded = load(&saved.0).unwrap();
+        assert_eq!(reloaded.map, loaded.map);
+        assert_eq!(reloaded.base_center, loaded.base_center);
+        assert_eq!(reloaded.spawns, team);
+    }
+
+    #[test]
+    fn malformed_maps_are_rejected() {
+        for (name, content) in [
+            ("uneven", "#####\n#.BBB#\n#.BBB\n#.BBB#\n"),
+            ("no_base", "###\n#.#\n###\n"),
+            ("broken_base", "#####\n#BBB#\n#B.B#\n#BBB#\n"),
+            ("unknown", "#####\n#BBB#\n#BBB?\n#BBB#\n"),
+            ("no_quantity", "######\n#BBBE#\n#BBB.#\n#BBB.#\n"),
+            (
+                "robot_outside",
+                "#####\n#BBB#\n#BBB#\n#BBB#\n; robot O 9 1\n",
+            ),
+            (
+                "robot_unknown",
+                "#####\n#BBB#\n#BBB#\n#BBB#\n; robot Z 1 1\n",
+            ),
+        ] {
+            let file = TempFile::new(name, content);
+            assert!(
+                matches!(load(&file.0), Err(SimulationError::InvalidMap(_))),
+                "{name}"
+            );
+        }
+    }
+}
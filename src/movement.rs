@@ -0,0 +1,123 @@
+use crate::robot::RobotPosition;
+
+/// Poids d'un pas droit dans les recherches de chemin, multiplié par le coût du terrain.
+pub const STRAIGHT: u32 = 10;
+/// Poids d'un pas en diagonale, environ √2 pas droits.
+pub const DIAGONAL: u32 = 14;
+
+const CARDINAL_OFFSETS: [(i16, i16); 4] = [(1, 0), (-1, 0), (0, 1), (0, -1)];
+const OCTILE_OFFSETS: [(i16, i16); 8] = [
+    (1, 0),
+    (-1, 0),
+    (0, 1),
+    (0, -1),
+    (1, 1),
+    (1, -1),
+    (-1, 1),
+    (-1, -1),
+];
+
+/// Poids d'un pas entre deux cases voisines, avant le coût du terrain.
+pub fn step_weight(from: (u16, u16), to: (u16, u16)) -> u32 {
+    if from.0 != to.0 && from.1 != to.1 {
+        DIAGONAL
+    } else {
+        STRAIGHT
+    }
+}
+
+/// Modèle de déplacement des robots (`--movement cardinal|octile`).
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Movement {
+    /// Quatre directions, distance de Manhattan.
+    #[default]
+    Cardinal,
+    /// Huit directions, diagonales comprises, distance octile.
+    Octile,
+}
+
+impl std::str::FromStr for Movement {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "cardinal" => Ok(Movement::Cardinal),
+            "octile" => Ok(Movement::Octile),
+            other => Err(format!("modèle de déplacement inconnu: {other}")),
+        }
+    }
+}
+
+impl Movement {
+    fn offsets(self) -> &'static [(i16, i16)] {
+        match self {
+            Movement::Cardinal => &CARDINAL_OFFSETS,
+            Movement::Octile => &OCTILE_OFFSETS,
+        }
+    }
+
+    /// Pas possibles depuis `from` et leur poids (`STRAIGHT` ou `DIAGONAL` fois le coût de la
+    /// case d'arrivée) ; `cost` vaut `None` pour une case infranchissable. Un pas en diagonale
+    /// ne coupe pas de coin : les deux cases qu'il longe doivent être franchissables.
+    pub fn steps(
+        self,
+        from: RobotPosition,
+        cost: impl Fn(RobotPosition) -> Option<u16>,
+    ) -> Vec<(RobotPosition, u32)> {
+        self.offsets()
+            .iter()
+            .filter_map(|&(dx, dy)| {
+                let next = RobotPosition(
+                    from.0.checked_add_signed(dx)?,
+                    from.1.checked_add_signed(dy)?,
+                );
+                let terrain = cost(next)? as u32;
+                if dx == 0 || dy == 0 {
+                    return Some((next, STRAIGHT * terrain));
+                }
+                let corners = [RobotPosition(next.0, from.1), RobotPosition(from.0, next.1)];
+                corners
+                    .into_iter()
+                    .all(|corner| cost(corner).is_some())
+                    .then_some((next, DIAGONAL * terrain))
+            })
+            .collect()
+    }
+
+    /// Les cases voisines en un pas, sans tenir compte du terrain.
+    pub fn neighbours(self, from: RobotPosition) -> impl Iterator<Item = RobotPosition> {
+        self.offsets().iter().filter_map(move |&(dx, dy)| {
+            Some(RobotPosition(
+                from.0.checked_add_signed(dx)?,
+                from.1.checked_add_signed(dy)?,
+            ))
+        })
+    }
+
+    /// `to` est à un pas de `from`.
+    pub fn is_step(self, from: RobotPosition, to: RobotPosition) -> bool {
+        let (dx, dy) = (from.0.abs_diff(to.0), from.1.abs_diff(to.1));
+        match self {
+            Movement::Cardinal => dx + dy == 1,
+            Movement::Octile => dx.max(dy) == 1,
+        }
+    }
+
+    /// Nombre minimal de pas entre deux cases (Manhattan ou Chebyshev).
+    pub fn min_steps(self, from: RobotPosition, to: RobotPosition) -> u16 {
+        let (dx, dy) = (from.0.abs_diff(to.0), from.1.abs_diff(to.1));
+        match self {
+            Movement::Cardinal => dx + dy,
+            Movement::Octile => dx.max(dy),
+        }
+    }
+
+    /// Borne inférieure du poids d'un trajet (Manhattan ou octile), pour l'A*.
+    pub fn heuristic(self, from: RobotPosition, to: RobotPosition) -> u32 {
+        let (dx, dy) = (from.0.abs_diff(to.0) as u32, from.1.abs_diff(to.1) as u32);
+        match self {
+            Movement::Cardinal => STRAIGHT * (dx + dy),
+            Movement::Octile => STRAIGHT * dx.max(dy) + (DIAGONAL - STRAIGHT) * dx.min(dy),
+        }
+    }
+}
@@ -0,0 +1,270 @@
+use crate::config::SpecParams;
+use crate::map::{self, Cell, Deposit};
+use crate::resources::{REGISTRY, Resource};
+use noise::{NoiseFn, Perlin};
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng, rngs::StdRng};
+
+/// Place les gisements des ressources du registre sur une carte déjà générée.
+pub trait ResourcePlacement {
+    fn name(&self) -> &'static str;
+    /// Gisements `(x, y, gisement)` posés sur `candidates` (sol atteignable).
+    fn place(
+        &self,
+        candidates: &[(u16, u16)],
+        base_center: (u16, u16),
+        rng: &mut StdRng,
+    ) -> Vec<(u16, u16, Deposit)>;
+}
+
+/// Noms acceptés par `from_spec`.
+pub const PLACEMENT_NAMES: [&str; 4] = ["uniform", "clusters", "noise", "depth"];
+
+/// Stratégie choisie et options communes à toutes les stratégies.
+pub struct Placement {
+    pub strategy: Box<dyn ResourcePlacement>,
+    pub seed: u64,
+    /// Nombre minimal de gisements par type de ressource (option `min_<clé>`),
+    /// indexé comme `resources::REGISTRY`.
+    pub minimums: Vec<usize>,
+}
+
+/// Construit une stratégie depuis `nom[:clé=valeur,...]`, ex. `clusters:veins=6,min_crystals=4`.
+pub fn from_spec(spec: &str) -> Result<Placement, String> {
+    let (name, mut params) = SpecParams::parse(spec)?;
+    let strategy: Box<dyn ResourcePlacement> = match name {
+        "uniform" => Box::new(Uniform {
+            rate: params.take("rate", 0.008)?,
+        }),
+        "clusters" => Box::new(Clusters {
+            veins: params.take("veins", 8)?,
+            length: params.take("length", 6)?,
+        }),
+        "noise" => Box::new(NoiseDensity {
+            noise_seed: params.take("noise_seed", 7)?,
+            scale: params.take("scale", 0.08)?,
+            rate: params.take("rate", 0.018)?,
+        }),
+        "depth" => Box::new(DepthScaled {
+            rate: params.take("rate", 0.010)?,
+        }),
+        other => {
+            return Err(format!(
+                "placement inconnu: {other} (disponibles: {})",
+                PLACEMENT_NAMES.join(", ")
+            ));
+        }
+    };
+    let seed = params.take("seed", rand::random())?;
+    let minimums = REGISTRY
+        .iter()
+        .map(|kind| params.take(&format!("min_{}", kind.key), 0))
+        .collect::<Result<_, _>>()?;
+    let placement = Placement {
+        strategy,
+        seed,
+        minimums,
+    };
+    params.finish(name)?;
+    Ok(placement)
+}
+
+fn deposit(resource: Resource, rng: &mut StdRng) -> Deposit {
+    Deposit::new(resource, rng.gen_range(5..=10))
+}
+
+impl Placement {
+    /// Pose les gisements sur le sol atteignable depuis la base, puis complète jusqu'aux minimums.
+    pub fn apply(&self, map: &mut [Vec<Cell>], base_center: (u16, u16)) {
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let reachable = map::reachable_from(map, base_center);
+        let mut candidates: Vec<(u16, u16)> = reachable
+            .into_iter()
+            .filter(|&(x, y)| map[y as usize][x as usize].is_free_floor())
+            .collect();
+        // L'ordre d'un HashSet varie d'une exécution à l'autre : on trie pour que la graine suffise
+        candidates.sort_unstable();
+
+        let mut placed = 0;
+        for (x, y, deposit) in self.strategy.place(&candidates, base_center, &mut rng) {
+            let cell = &mut map[y as usize][x as usize];
+            if cell.is_free_floor() {
+                cell.deposit = Some(deposit);
+                placed += 1;
+            }
+        }
+
+        let count = |map: &[Vec<Cell>], resource: Resource| {
+            map.iter()
+                .flatten()
+                .filter(|cell| cell.deposit.is_some_and(|d| d.resource == resource))
+                .count()
+        };
+        let missing: Vec<(Resource, usize)> = Resource::all()
+            .zip(&self.minimums)
+            .map(|(resource, &min)| (resource, min.saturating_sub(count(map, resource))))
+            .collect();
+        let mut free: Vec<(u16, u16)> = candidates
+            .into_iter()
+            .filter(|&(x, y)| map[y as usize][x as usize].is_free_floor())
+            .collect();
+        free.shuffle(&mut rng);
+        let mut free = free.into_iter();
+        for &(resource, amount) in &missing {
+            for (x, y) in free.by_ref().take(amount) {
+                map[y as usize][x as usize].deposit = Some(deposit(resource, &mut rng));
+            }
+        }
+
+        let added: Vec<String> = missing
+            .iter()
+            .filter(|(_, amount)| *amount > 0)
+            .map(|(resource, amount)| format!("{} {}", amount, resource.kind().key))
+            .collect();
+        tracing::info!(
+            "Placement {} (graine {}): {} gisements, ajoutés pour les minimums: [{}]",
+            self.strategy.name(),
+            self.seed,
+            placed,
+            added.join(", ")
+        );
+    }
+}
+
+/// Tirage indépendant par case (comportement historique), type selon la rareté.
+pub struct Uniform {
+    pub rate: f64,
+}
+
+impl ResourcePlacement for Uniform {
+    fn name(&self) -> &'static str {
+        "uniform"
+    }
+
+    fn place(
+        &self,
+        candidates: &[(u16, u16)],
+        _base_center: (u16, u16),
+        rng: &mut StdRng,
+    ) -> Vec<(u16, u16, Deposit)> {
+        let mut sources = Vec::new();
+        for &(x, y) in candidates {
+            let roll: f64 = rng.r#gen(); // nombre entre 0.0 et 1.0
+            if roll < self.rate {
+                let resource = Resource::random(rng);
+                sources.push((x, y, deposit(resource, rng)));
+            }
+        }
+        sources
+    }
+}
+
+/// Filons : marches aléatoires d'un même type de ressource autour d'un point de départ.
+pub struct Clusters {
+    pub veins: usize,
+    pub length: usize,
+}
+
+impl ResourcePlacement for Clusters {
+    fn name(&self) -> &'static str {
+        "clusters"
+    }
+
+    fn place(
+        &self,
+        candidates: &[(u16, u16)],
+        _base_center: (u16, u16),
+        rng: &mut StdRng,
+    ) -> Vec<(u16, u16, Deposit)> {
+        let mut sources = Vec::new();
+        if candidates.is_empty() {
+            return sources;
+        }
+        for _ in 0..self.veins {
+            let resource = Resource::random(rng);
+            let mut current = candidates[rng.gen_range(0..candidates.len())];
+            for _ in 0..self.length {
+                sources.push((current.0, current.1, deposit(resource, rng)));
+                let (dx, dy) = [(1i16, 0i16), (-1, 0), (0, 1), (0, -1)][rng.gen_range(0..4)];
+                let next = (
+                    current.0.saturating_add_signed(dx),
+                    current.1.saturating_add_signed(dy),
+                );
+                // Le filon ne suit que le sol atteignable
+                if candidates.binary_search(&next).is_ok() {
+                    current = next;
+                }
+            }
+        }
+        sources
+    }
+}
+
+/// Densité modulée par un bruit de Perlin : zones riches et zones désertes.
+pub struct NoiseDensity {
+    pub noise_seed: u32,
+    pub scale: f64,
+    pub rate: f64,
+}
+
+impl ResourcePlacement for NoiseDensity {
+    fn name(&self) -> &'static str {
+        "noise"
+    }
+
+    fn place(
+        &self,
+        candidates: &[(u16, u16)],
+        _base_center: (u16, u16),
+        rng: &mut StdRng,
+    ) -> Vec<(u16, u16, Deposit)> {
+        let perlin = Perlin::new(self.noise_seed);
+        let mut sources = Vec::new();
+        for &(x, y) in candidates {
+            // Bruit ramené de [-1, 1] à [0, 1], élevé au carré pour contraster
+            let density = ((perlin.get([x as f64 * self.scale, y as f64 * self.scale, 0.5]) + 1.0)
+                / 2.0)
+                .powi(2);
+            let roll: f64 = rng.r#gen();
+            if roll < self.rate * density {
+                let resource = Resource::random(rng);
+                sources.push((x, y, deposit(resource, rng)));
+            }
+        }
+        sources
+    }
+}
+
+/// Ressources courantes près de la base ; les plus rares deviennent aussi fréquentes
+/// que les autres en s'éloignant.
+pub struct DepthScaled {
+    pub rate: f64,
+}
+
+impl ResourcePlacement for DepthScaled {
+    fn name(&self) -> &'static str {
+        "depth"
+    }
+
+    fn place(
+        &self,
+        candidates: &[(u16, u16)],
+        base_center: (u16, u16),
+        rng: &mut StdRng,
+    ) -> Vec<(u16, u16, Deposit)> {
+        let distance =
+            |&(x, y): &(u16, u16)| (x.abs_diff(base_center.0) + y.abs_diff(base_center.1)) as f64;
+        let max_distance = candidates.iter().map(distance).fold(1.0, f64::max);
+        let mut sources = Vec::new();
+        for pos in candidates {
+            let depth = distance(pos) / max_distance;
+            let roll: f64 = rng.r#gen();
+            if roll < self.rate {
+                // Au loin, les poids tendent vers 1 : la rareté s'efface
+                let resource = Resource::pick(rng, |kind| kind.rarity.powf(1.0 - depth));
+                sources.push((pos.0, pos.1, deposit(resource, rng)));
+            }
+        }
+        sources
+    }
+}
@@ -0,0 +1,135 @@
+use std::collections::{HashMap, VecDeque};
+
+use pathfinding::prelude::astar;
+
+use crate::map::Cell;
+use crate::robot::{self, Robot, RobotPosition};
+
+/// Planification des trajets des collecteurs (`--planner independent|cooperative`).
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Planner {
+    /// Chaque collecteur calcule son chemin seul (`robot::go_to_nearest_point`).
+    #[default]
+    Independent,
+    /// A* espace-temps avec table de réservation partagée (voir `cooperative`).
+    Cooperative,
+}
+
+impl std::str::FromStr for Planner {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "independent" => Ok(Planner::Independent),
+            "cooperative" => Ok(Planner::Cooperative),
+            other => Err(format!("planificateur inconnu: {other}")),
+        }
+    }
+}
+
+/// Chemin planifié gardé d'un tick à l'autre : le robot le suit pas à pas et ne le
+/// recalcule que si ce qu'il sait des cases restantes a changé.
+#[derive(Clone, Debug)]
+pub struct PlannedPath {
+    pub target: RobotPosition,
+    /// Cases restantes, la prochaine en tête, avec leur coût connu au moment du calcul
+    /// (`None` pour une cible inconnue ou infranchissable, visée quand même).
+    steps: VecDeque<(RobotPosition, Option<u16>)>,
+}
+
+impl PlannedPath {
+    /// Prochaine case du chemin.
+    pub fn next(&self) -> Option<RobotPosition> {
+        self.steps.front().map(|&(position, _)| position)
+    }
+
+    /// Le chemin mène toujours à `target` depuis la position du robot, et aucune case
+    /// restante n'a changé de coût dans ses connaissances.
+    pub fn is_valid(
+        &self,
+        robot: &Robot,
+        target: RobotPosition,
+        known: &HashMap<(u16, u16), Cell>,
+    ) -> bool {
+        if self.target != target {
+            return false;
+        }
+        let Some(next) = self.next() else {
+            return robot.position == target;
+        };
+        robot.movement.is_step(robot.position, next)
+            && self.steps.iter().all(|&(position, cost)| {
+                robot::known_cost(&robot.robot_type, robot::known_cell(robot, known, position))
+                    == cost
+            })
+    }
+
+    /// Avance d'une case et la retourne.
+    pub fn advance(&mut self) -> Option<RobotPosition> {
+        self.steps.pop_front().map(|(position, _)| position)
+    }
+}
+
+/// Recherche de chemin restée sans résultat. Tant que le robot n'a pas bougé et que les
+/// connaissances de l'essaim n'ont pas changé (`FlowField::revision`), la relancer vers les
+/// mêmes cibles échouerait de même.
+#[derive(Clone, Debug)]
+pub struct FailedSearch {
+    position: RobotPosition,
+    revision: u64,
+    targets: Vec<RobotPosition>,
+}
+
+impl FailedSearch {
+    pub fn new(position: RobotPosition, revision: u64, targets: Vec<RobotPosition>) -> Self {
+        FailedSearch {
+            position,
+            revision,
+            targets,
+        }
+    }
+
+    /// Une recherche depuis `position` vers `targets` est déjà connue pour échouer.
+    pub fn covers(
+        &self,
+        position: RobotPosition,
+        revision: u64,
+        targets: &[RobotPosition],
+    ) -> bool {
+        self.position == position
+            && self.revision == revision
+            && targets.iter().all(|target| self.targets.contains(target))
+    }
+}
+
+/// A* sur les connaissances du robot (`robot::known_cell`) vers `target`, selon son modèle de déplacement ; la cible
+/// elle-même peut être inconnue.
+pub fn plan(
+    robot: &Robot,
+    target: RobotPosition,
+    known: &HashMap<(u16, u16), Cell>,
+) -> Option<PlannedPath> {
+    let (path, _cost) = astar(
+        &robot.position,
+        |p: &RobotPosition| {
+            robot.movement.steps(*p, |next| {
+                match robot::known_cost(&robot.robot_type, robot::known_cell(robot, known, next)) {
+                    Some(cost) => Some(cost),
+                    None if next == target => Some(1),
+                    None => None,
+                }
+            })
+        },
+        |p| robot.movement.heuristic(*p, target),
+        |p| *p == target,
+    )?;
+    let steps = path
+        .into_iter()
+        .skip(1)
+        .map(|position| {
+            let cell = robot::known_cell(robot, known, position);
+            (position, robot::known_cost(&robot.robot_type, cell))
+        })
+        .collect();
+    Some(PlannedPath { target, steps })
+}
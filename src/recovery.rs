@@ -0,0 +1,191 @@
+use std::collections::HashMap;
+
+use rand::seq::SliceRandom;
+use rand::{SeedableRng, rngs::StdRng};
+
+use crate::config::SpecParams;
+use crate::flow_field::FlowField;
+use crate::lifecycle::{self, RobotState};
+use crate::map::Cell;
+use crate::robot::{self, Robot};
+
+/// Ticks sans progrès avant qu'un robot soit considéré bloqué et tente de se dégager.
+pub const STUCK_AFTER: u32 = 20;
+/// Pas au hasard d'un robot bloqué avant de rentrer à la base, par défaut.
+const RANDOM_WALK_STEPS: u16 = 8;
+/// Ticks pendant lesquels un gisement hors d'atteinte n'est plus proposé aux collecteurs.
+pub const BLOCKED_FOR: u64 = 300;
+
+/// Stratégie de dégagement en cours.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Recovery {
+    /// Pas au hasard restants pour sortir d'une impasse.
+    RandomWalk(u16),
+    /// Retour à la base, où le robot reprend son rôle.
+    ReturnHome,
+}
+
+/// Pas au hasard des robots bloqués (`--recovery`), tirés d'une graine pour pouvoir rejouer
+/// une partie.
+pub struct RandomWalk {
+    /// Pas au hasard avant de rentrer à la base.
+    pub steps: u16,
+    pub seed: u64,
+    rng: StdRng,
+}
+
+impl RandomWalk {
+    /// `walk[:steps=8,seed=...]`.
+    pub fn from_spec(spec: &str) -> Result<Self, String> {
+        let (name, mut params) = SpecParams::parse(spec)?;
+        if name != "walk" {
+            return Err(format!("dégagement inconnu: {name} (disponible: walk)"));
+        }
+        let steps = params.take("steps", RANDOM_WALK_STEPS)?;
+        if steps == 0 {
+            return Err("steps doit valoir au moins 1".into());
+        }
+        let seed = params.take("seed", rand::random())?;
+        params.finish(name)?;
+        Ok(RandomWalk {
+            steps,
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+        })
+    }
+}
+
+impl Default for RandomWalk {
+    fn default() -> Self {
+        let seed = rand::random();
+        RandomWalk {
+            steps: RANDOM_WALK_STEPS,
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+}
+
+/// Robot sans progrès depuis `STUCK_AFTER` ticks et pas déjà en dégagement. Les robots
+/// en panne ou remorqués attendent un réparateur.
+pub fn is_stuck(robot: &Robot) -> bool {
+    !robot.broken && !robot.towed && robot.recovery.is_none() && robot.idle_ticks >= STUCK_AFTER
+}
+
+/// Débute le dégagement : le robot part au hasard puis rentrera à la base.
+pub fn start(robot: &mut Robot, walk: &RandomWalk) {
+    robot.recovery = Some(Recovery::RandomWalk(walk.steps));
+    robot.idle_ticks = 0;
+    lifecycle::enter(robot, RobotState::Stuck);
+}
+
+/// Un pas de dégagement.
+pub fn step(
+    robot: &mut Robot,
+    map: &[Vec<Cell>],
+    known: &HashMap<(u16, u16), Cell>,
+    home_field: &FlowField,
+    walk: &mut RandomWalk,
+) {
+    match robot.recovery {
+        Some(Recovery::RandomWalk(steps)) => {
+            let free = robot.movement.steps(robot.position, |p| {
+                map.get(p.1 as usize)
+                    .and_then(|row| row.get(p.0 as usize))
+                    .and_then(|cell| cell.move_cost(&robot.robot_type))
+            });
+            if let Some(&(next, _)) = free.choose(&mut walk.rng) {
+                robot.position = next;
+            }
+            robot.recovery = Some(if steps > 1 {
+                Recovery::RandomWalk(steps - 1)
+            } else {
+                Recovery::ReturnHome
+            });
+        }
+        Some(Recovery::ReturnHome) if robot.position == robot.home => {
+            robot.recovery = None;
+            lifecycle::enter(robot, RobotState::Idle);
+            tracing::info!("Robot {} dégagé, de retour à la base", robot.id);
+        }
+        Some(Recovery::ReturnHome) => {
+            robot::go_home(robot, known, home_field);
+        }
+        None => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::Terrain;
+    use crate::movement::Movement;
+    use crate::robot::RobotPosition;
+    use crate::roles;
+
+    /// Couloir fermé de cinq cases, la base à l'ouest.
+    fn corridor() -> Vec<Vec<Cell>> {
+        (0..3)
+            .map(|y| {
+                (0..7)
+                    .map(|x| {
+                        if y == 1 && (1..6).contains(&x) {
+                            Cell::new(Terrain::Floor)
+                        } else {
+                            Cell::new(Terrain::Wall)
+                        }
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn robot_without_progress_is_stuck_then_recovers_at_base() {
+        let map = corridor();
+        let known: HashMap<(u16, u16), Cell> = map
+            .iter()
+            .enumerate()
+            .flat_map(|(y, row)| {
+                row.iter()
+                    .enumerate()
+                    .map(move |(x, &cell)| ((x as u16, y as u16), cell))
+            })
+            .collect();
+        let home = RobotPosition(1, 1);
+        let mut home_field = FlowField::new(home, Movement::default());
+        home_field.update(&known, &known.keys().copied().collect::<Vec<_>>());
+
+        // Une cible derrière le mur du bout du couloir : le robot n'avance plus
+        let mut robot = robot::robots_collecteur(home);
+        robot.position = RobotPosition(5, 1);
+        robot.target_resource = Some(RobotPosition(6, 1));
+        let previous = robot.position;
+        for _ in 0..STUCK_AFTER {
+            assert!(!is_stuck(&robot));
+            roles::track_idle(&mut robot, previous);
+        }
+        assert!(is_stuck(&robot));
+
+        let mut walk = RandomWalk::from_spec("walk:seed=3").unwrap();
+        start(&mut robot, &walk);
+        assert_eq!(robot.state, RobotState::Stuck);
+        assert!(!is_stuck(&robot));
+        for _ in 0..walk.steps {
+            step(&mut robot, &map, &known, &home_field, &mut walk);
+            assert!(
+                map[robot.position.1 as usize][robot.position.0 as usize]
+                    .is_passable(&robot.robot_type)
+            );
+        }
+        assert_eq!(robot.recovery, Some(Recovery::ReturnHome));
+
+        for _ in 0..5 {
+            step(&mut robot, &map, &known, &home_field, &mut walk);
+        }
+        assert_eq!(robot.position, home);
+        step(&mut robot, &map, &known, &home_field, &mut walk);
+        assert_eq!(robot.recovery, None);
+        assert_eq!(robot.state, RobotState::Idle);
+    }
+}
@@ -0,0 +1,102 @@
+use rand::Rng;
+use ratatui::style::Color;
+
+/// Caractéristiques d'un type de ressource.
+pub struct ResourceKind {
+    /// Clé utilisée dans les options (`min_<clé>`) et les journaux.
+    pub key: &'static str,
+    pub name: &'static str,
+    /// Lettre sur la carte et dans les fichiers de carte, suivie de la quantité (`E5`).
+    pub glyph: char,
+    /// Couleur d'un gisement pas encore signalé.
+    pub color: Color,
+    /// Couleur d'un gisement signalé à la base.
+    pub known_color: Color,
+    /// Poids dans les tirages : plus il est faible, plus la ressource est rare.
+    pub rarity: f64,
+    /// Ticks de minage pour extraire une unité.
+    pub mining_time: u32,
+    /// Niveau d'outil minimal du collecteur (0 : outil de base).
+    pub tool_level: u8,
+    /// Points rapportés par unité livrée à la base.
+    pub value: u32,
+}
+
+/// Registre des ressources : ajouter une entrée suffit à créer un nouveau type.
+/// Les lettres doivent rester distinctes des autres symboles des fichiers de carte
+/// (`#`, `.`, `:`, `~`, `%`, `B`, et `RobotType::glyph`) et des touches du TUI.
+pub const REGISTRY: &[ResourceKind] = &[
+    ResourceKind {
+        key: "energy",
+        name: "Énergie",
+        glyph: 'E',
+        color: Color::Green,
+        known_color: Color::Blue,
+        rarity: 0.55,
+        mining_time: 1,
+        tool_level: 0,
+        value: 1,
+    },
+    ResourceKind {
+        key: "crystals",
+        name: "Cristaux",
+        glyph: 'C',
+        color: Color::LightMagenta,
+        known_color: Color::Yellow,
+        rarity: 0.33,
+        mining_time: 3,
+        tool_level: 1,
+        value: 3,
+    },
+];
+
+/// Type de ressource : indice dans `REGISTRY`.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct Resource(pub u8);
+
+impl Resource {
+    /// Les sources d'énergie sont les seules à se régénérer.
+    pub const ENERGY: Resource = Resource(0);
+    /// Monnaie des améliorations achetées par la base.
+    pub const CRYSTALS: Resource = Resource(1);
+
+    pub fn kind(self) -> &'static ResourceKind {
+        &REGISTRY[self.0 as usize]
+    }
+
+    pub fn all() -> impl Iterator<Item = Resource> {
+        (0..REGISTRY.len() as u8).map(Resource)
+    }
+
+    pub fn from_glyph(glyph: char) -> Option<Resource> {
+        Resource::all().find(|resource| resource.kind().glyph == glyph)
+    }
+
+    /// Tirage pondéré par `weight`.
+    pub fn pick(rng: &mut impl Rng, weight: impl Fn(&ResourceKind) -> f64) -> Resource {
+        let total: f64 = REGISTRY.iter().map(&weight).sum();
+        let mut roll = rng.r#gen::<f64>() * total;
+        for resource in Resource::all() {
+            let w = weight(resource.kind());
+            if roll < w {
+                return resource;
+            }
+            roll -= w;
+        }
+        Resource(REGISTRY.len() as u8 - 1)
+    }
+
+    /// Tirage selon la rareté du registre.
+    pub fn random(rng: &mut impl Rng) -> Resource {
+        Resource::pick(rng, |kind| kind.rarity)
+    }
+}
+
+/// Points d'une quantité livrée par type de ressource (indices du registre).
+pub fn score(totals: &[u32]) -> u32 {
+    totals
+        .iter()
+        .zip(REGISTRY)
+        .map(|(amount, kind)| amount * kind.value)
+        .sum()
+}
@@ -1,86 +1,192 @@
 use std::collections::{HashMap, HashSet};
 use std::hash::Hash;
 
-use crate::map::Tile;
+use crate::cooperative::{self, Reservations, Route};
+use crate::docking::{self, DockState, Docking};
+use crate::flow_field::FlowField;
+use crate::lifecycle::{self, RobotState};
+use crate::map::{Cell, Terrain};
+use crate::movement::Movement;
+use crate::planning::{self, FailedSearch, PlannedPath};
+use crate::recovery::Recovery;
+use crate::resources::Resource;
+use crate::upgrades::{self, Upgrades};
 
 use crate::base::BaseMessage;
-use pathfinding::prelude::astar;
 use pathfinding::prelude::bfs;
+use pathfinding::prelude::dijkstra;
 use tokio::sync::mpsc::Sender;
 
 pub struct Robot {
+    /// Identifiant stable, attribué par `GameState::new`.
+    pub id: usize,
     pub position: RobotPosition,
     pub robot_type: RobotType,
-    pub map_discovered: HashMap<(u16, u16), Tile>,
-    pub found_resources: bool,
+    /// Cases connues du robot, telles qu'il les a vues ou que l'essaim les lui a transmises.
+    pub map_discovered: HashMap<(u16, u16), Cell>,
+    /// Ressource repérée par un éclaireur, à signaler à la base (la case est dans `target_resource`).
+    pub found_resource: Option<Resource>,
     pub collected_resources: u32,
     pub target_resource: Option<RobotPosition>,
-    pub carried_resource: Option<Tile>,
+    /// Ressource de la cargaison (collecteurs et transporteurs).
+    pub carried_resource: Option<Resource>,
     pub direction: Option<(i16, i16)>,
+    /// Centre de la base où le robot rapporte découvertes et cargaisons.
+    pub home: RobotPosition,
+    /// Ticks restants avant de pouvoir quitter un terrain lent.
+    pub move_cooldown: u16,
+    /// Niveau de l'outil de minage (collecteurs) ; certains gisements demandent un outil amélioré.
+    pub tool_level: u8,
+    /// Ticks passés sans bouger alors qu'il a une tâche (voir `roles::is_stranded`).
+    pub idle_ticks: u32,
+    /// Remorqué par un réparateur : le robot n'agit plus jusqu'à la base.
+    pub towed: bool,
+    /// Identifiant du robot remorqué (réparateur).
+    pub towing: Option<usize>,
+    /// Améliorations installées, reprises de la flotte à chaque passage à la base.
+    pub upgrades: Upgrades,
+    /// Batterie : un point par pas, rechargée à la base.
+    pub energy: u32,
+    /// État du cycle de vie, fixé quand le comportement du robot change (voir `lifecycle`).
+    pub state: RobotState,
+    /// Chemin suivi par `go_to_nearest_point`, gardé tant qu'il reste valable.
+    pub path: Option<PlannedPath>,
+    /// Dégagement en cours après un blocage (voir `recovery`).
+    pub recovery: Option<Recovery>,
+    /// En panne : le robot n'agit plus jusqu'à sa réparation à la base.
+    pub broken: bool,
+    /// Pas faits depuis le dernier passage à la base (usure, voir `failures::Reliability`).
+    pub moves: u32,
+    /// Livraison en cours : file d'attente, poste attribué ou déchargement (voir `docking`).
+    pub dock: Option<DockState>,
+    /// Itinéraire daté du planificateur coopératif (voir `cooperative`).
+    pub route: Option<Route>,
+    /// Après un échec du planificateur coopératif, tick avant lequel il n'est pas relancé :
+    /// le collecteur suit son propre chemin en attendant.
+    pub route_retry: u64,
+    /// Dernière recherche de chemin sans résultat, pour ne pas la relancer à l'identique à chaque tick.
+    pub failed_search: Option<FailedSearch>,
+    /// Quatre ou huit directions (`--movement`), pour toutes les recherches de chemin.
+    pub movement: Movement,
 }
 
-#[derive(PartialEq)]
+#[derive(Clone, Copy, PartialEq, Debug)]
 pub enum RobotType {
     Eclaireur,
     Collecteur,
+    /// Rapporte à la base les cargaisons laissées par les collecteurs loin de la base.
+    Transporteur,
+    /// Creuse un passage vers les gisements inaccessibles.
+    Batisseur,
+    /// Ramène à la base les robots immobilisés.
+    Reparateur,
+}
+
+impl RobotType {
+    pub const ALL: [RobotType; 5] = [
+        RobotType::Eclaireur,
+        RobotType::Collecteur,
+        RobotType::Transporteur,
+        RobotType::Batisseur,
+        RobotType::Reparateur,
+    ];
+
+    /// Lettre du robot sur la carte et dans les fichiers de carte.
+    pub fn glyph(self) -> char {
+        match self {
+            RobotType::Eclaireur => 'X',
+            RobotType::Collecteur => 'O',
+            RobotType::Transporteur => 'T',
+            RobotType::Batisseur => 'K',
+            RobotType::Reparateur => 'R',
+        }
+    }
+
+    pub fn from_glyph(glyph: char) -> Option<RobotType> {
+        RobotType::ALL.into_iter().find(|t| t.glyph() == glyph)
+    }
 }
 
 #[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
 pub struct RobotPosition(pub u16, pub u16);
 
 impl RobotPosition {
-    fn distance(&self, other: &RobotPosition) -> u16 {
+    pub fn distance(&self, other: &RobotPosition) -> u16 {
         self.0.abs_diff(other.0) + self.1.abs_diff(other.1)
     }
+}
 
-    fn successors(&self) -> Vec<(RobotPosition, u16)> {
-        let &RobotPosition(x, y) = self;
-        let mut moves = Vec::new();
-        for (dx, dy) in &[(1, 0), (-1, 0), (0, 1), (0, -1)] {
-            let nx = x as i16 + dx;
-            let ny = y as i16 + dy;
-            if nx >= 0 && ny >= 0 {
-                moves.push((RobotPosition(nx as u16, ny as u16), 1));
-            }
-        }
-        moves
-    }
+/// Le robot est sur l'une des neuf cases de la base (centre ou poste d'accostage).
+pub fn at_base(robot: &Robot) -> bool {
+    robot.position.0.abs_diff(robot.home.0) <= 1 && robot.position.1.abs_diff(robot.home.1) <= 1
 }
 
-pub fn robots_eclaireur(width: u16, height: u16, direction: (i16, i16)) -> Robot {
-    let center_map: RobotPosition = RobotPosition(width / 2, height / 2);
+/// Robot d'un type donné, sur la base, sans direction privilégiée.
+pub fn robots_role(robot_type: RobotType, home: RobotPosition) -> Robot {
     Robot {
-        position: center_map,
-        robot_type: RobotType::Eclaireur,
+        id: 0,
+        position: home,
+        robot_type,
         map_discovered: HashMap::new(),
-        found_resources: false,
+        found_resource: None,
         collected_resources: 0,
         target_resource: None,
         carried_resource: None,
-        direction: Some(direction),
+        direction: None,
+        home,
+        move_cooldown: 0,
+        tool_level: 0,
+        idle_ticks: 0,
+        towed: false,
+        towing: None,
+        upgrades: Upgrades::default(),
+        energy: upgrades::BASE_ENERGY,
+        state: RobotState::default(),
+        path: None,
+        recovery: None,
+        broken: false,
+        moves: 0,
+        dock: None,
+        route: None,
+        route_retry: 0,
+        failed_search: None,
+        movement: Movement::default(),
     }
 }
 
-pub fn robots_collecteur(width: u16, height: u16) -> Robot {
-    let center_map: RobotPosition = RobotPosition(width / 2, height / 2);
+pub fn robots_eclaireur(home: RobotPosition, direction: (i16, i16)) -> Robot {
     Robot {
-        position: center_map,
-        robot_type: RobotType::Collecteur,
-        map_discovered: HashMap::new(),
-        found_resources: false,
-        collected_resources: 0,
-        target_resource: None,
-        carried_resource: None,
-        direction: None,
+        direction: Some(direction),
+        ..robots_role(RobotType::Eclaireur, home)
     }
 }
 
+pub fn robots_collecteur(home: RobotPosition) -> Robot {
+    robots_role(RobotType::Collecteur, home)
+}
+
+/// Équipe les `count` premiers collecteurs de l'outil amélioré (niveau 1).
+pub fn upgrade_tools(robots: &mut [Robot], count: usize) {
+    for robot in robots
+        .iter_mut()
+        .filter(|robot| robot.robot_type == RobotType::Collecteur)
+        .take(count)
+    {
+        robot.tool_level = 1;
+    }
+}
+
+/// Le collecteur a l'outil requis pour miner cette ressource.
+pub fn can_mine(robot: &Robot, resource: Resource) -> bool {
+    robot.robot_type == RobotType::Collecteur && robot.tool_level >= resource.kind().tool_level
+}
+
 pub fn robot_vision(
     robot: &Robot,
-    map: &[Vec<Tile>],
+    map: &[Vec<Cell>],
     width: u16,
     height: u16,
-) -> HashMap<(u16, u16), Tile> {
+) -> HashMap<(u16, u16), Cell> {
     let RobotPosition(rx, ry) = robot.position;
     let mut map_around = HashMap::new();
 
@@ -92,103 +198,158 @@ pub fn robot_vision(
         (1, 0),        // droite
     ];
 
+    // La vue porte jusqu'au rayon de vision, un mur arrête le regard
+    let radius = robot.upgrades.vision_radius() as i16;
     for (dx, dy) in directions {
-        let nx = rx as i16 + dx;
-        let ny = ry as i16 + dy;
-        if nx >= 0 && ny >= 0 && (nx as u16) < width && (ny as u16) < height {
-            map_around.insert(
-                (nx as u16, ny as u16),
-                map[ny as usize][nx as usize].clone(),
-            );
+        for step in 1..=radius {
+            let nx = rx as i16 + dx * step;
+            let ny = ry as i16 + dy * step;
+            if nx < 0 || ny < 0 || (nx as u16) >= width || (ny as u16) >= height {
+                break;
+            }
+            let cell = map[ny as usize][nx as usize];
+            map_around.insert((nx as u16, ny as u16), cell);
+            if cell.terrain == Terrain::Wall {
+                break;
+            }
         }
     }
 
     map_around
 }
 
-pub fn collect_resources(
-    robot: &mut Robot,
-    map: &mut [Vec<Tile>],
+/// Cases que le robot perçoit à son tour : sa vue, sa case et celles où il peut faire un pas.
+pub fn perceived_cells(
+    robot: &Robot,
+    map: &[Vec<Cell>],
     width: u16,
     height: u16,
+) -> HashMap<(u16, u16), Cell> {
+    let mut cells = robot_vision(robot, map, width, height);
+    let around = std::iter::once(robot.position).chain(robot.movement.neighbours(robot.position));
+    for position in around.filter(|p| p.0 < width && p.1 < height) {
+        cells.insert(
+            (position.0, position.1),
+            map[position.1 as usize][position.0 as usize],
+        );
+    }
+    cells
+}
+
+/// Rapporte la cargaison à la base : le robot obtient un poste d'amarrage (ou attend son tour
+/// près de la base), s'y rend, décharge puis attend l'accusé de la base (voir `docking`).
+/// Retourne `false` s'il n'a rien à livrer.
+pub fn deliver_cargo(
+    robot: &mut Robot,
+    docking: &mut Docking,
+    known: &HashMap<(u16, u16), Cell>,
+    home_field: &FlowField,
+    traffic: Option<&mut Reservations>,
+    tx_base: &Sender<BaseMessage>,
+) -> bool {
+    if robot.collected_resources == 0 {
+        return false;
+    }
+    // La livraison avant la recharge fait partie du retour pour recharger
+    if robot.state != RobotState::Charging {
+        lifecycle::enter(robot, RobotState::ReturningWithCargo);
+    }
+    if robot.dock.is_none() {
+        docking.request(robot);
+    }
+    match robot.dock {
+        Some(DockState::Queued) if !docking::is_waiting_area(robot) => {
+            travel(robot, robot.home, known, home_field, traffic);
+        }
+        Some(DockState::Assigned(slot)) if robot.position == slot => docking.dock(robot, tx_base),
+        Some(DockState::Assigned(slot)) => travel(robot, slot, known, home_field, traffic),
+        Some(DockState::Unloading(..) | DockState::Confirming(..)) => {
+            docking.unload(robot, tx_base)
+        }
+        Some(DockState::Queued) | None => {}
+    }
+    true
+}
+
+pub fn collect_resources(
+    robot: &mut Robot,
+    map: &mut [Vec<Cell>],
+    known: &HashMap<(u16, u16), Cell>,
+    home_field: &FlowField,
+    docking: &mut Docking,
+    traffic: Option<&mut Reservations>,
     tx_base: &Sender<BaseMessage>,
-    reserved: &HashSet<(u16, u16)>,
 ) {
-    let base = RobotPosition(width / 2, height / 2);
+    // Une livraison entamée est menée à son terme ; sinon la soute pleine, ou plus de cible
+    // pour la compléter, ramène le robot à la base
+    if robot.collected_resources > 0
+        && (robot.dock.is_some()
+            || robot.collected_resources >= robot.upgrades.cargo_capacity()
+            || robot.target_resource.is_none())
+    {
+        deliver_cargo(robot, docking, known, home_field, traffic, tx_base);
+        return;
+    }
 
     let Some(target) = robot.target_resource else {
         tracing::info!(" Pas de target définie");
+        lifecycle::enter(robot, RobotState::Idle);
         return;
     };
 
-    if matches!(map[target.1 as usize][target.0 as usize], Tile::Explored) {
+    if map[target.1 as usize][target.0 as usize]
+        .deposit_left()
+        .is_none()
+    {
         tracing::info!(
             " Ressource ({}, {}) déjà collectée → RESET TARGET",
             target.0,
             target.1
         );
         robot.target_resource = None;
-        robot.collected_resources = 0;
-        robot.carried_resource = None;
         tracing::info!(" Target reset à None");
-        find_nearest_resource(robot, &robot.map_discovered, reserved);
-        return;
-    }
-
-    if robot.position == base && robot.collected_resources > 0 {
-        let amount = robot.collected_resources;
-        robot.collected_resources = 0;
-        let resource_type = robot.carried_resource.clone().unwrap();
-        robot.carried_resource = None;
-
-        let _ = tx_base.try_send(BaseMessage::Collected {
-            resource: resource_type,
-            amount,
-        });
-
-        tracing::info!(" Déposé {} unités", amount);
-        return;
-    }
-
-    if robot.collected_resources > 0 && robot.position != base {
-        go_to_nearest_point(robot, base);
         return;
     }
 
     if robot.position != target {
-        go_to_nearest_point(robot, target);
+        lifecycle::enter(robot, RobotState::GoingToDeposit);
+        travel(robot, target, known, home_field, traffic);
         return;
     }
+    lifecycle::enter(robot, RobotState::Mining);
 
     let (tx, ty) = (target.0 as usize, target.1 as usize);
-
-    match &mut map[ty][tx] {
-        Tile::SourceFound(qty) if *qty > 0 => {
-            *qty -= 1;
-            robot.collected_resources += 1;
-            robot.carried_resource = Some(Tile::Source(0));
-
-            if *qty == 0 {
-                map[ty][tx] = Tile::Explored;
-                robot
-                    .map_discovered
-                    .insert((tx as u16, ty as u16), Tile::Explored);
-                robot.target_resource = None;
-                tracing::info!("Source épuisée");
-            }
+    let cell = &mut map[ty][tx];
+
+    match &mut cell.deposit {
+        Some(deposit) if deposit.qty > 0 && !can_mine(robot, deposit.resource) => {
+            tracing::warn!(
+                "{} demande un outil de niveau {}",
+                deposit.resource.kind().name,
+                deposit.resource.kind().tool_level
+            );
+            robot.target_resource = None;
         }
-        Tile::CristalFound(qty) if *qty > 0 => {
-            *qty -= 1;
+        Some(deposit) if deposit.qty > 0 => {
+            // Une unité demande `mining_time` ticks ; la progression reste sur le gisement
+            deposit.progress += 1;
+            if deposit.progress < deposit.resource.kind().mining_time {
+                return;
+            }
+            deposit.progress = 0;
+            deposit.qty -= 1;
             robot.collected_resources += 1;
-            robot.carried_resource = Some(Tile::Cristal(0));
+            robot.carried_resource = Some(deposit.resource);
 
-            if *qty == 0 {
-                map[ty][tx] = Tile::Explored;
-                robot
-                    .map_discovered
-                    .insert((tx as u16, ty as u16), Tile::Explored);
+            if deposit.qty == 0 {
+                let resource = deposit.resource;
+                cell.deposit = None;
+                robot.map_discovered.insert((tx as u16, ty as u16), *cell);
                 robot.target_resource = None;
-                tracing::info!("Cristal épuisé");
+                let _ = tx_base.try_send(BaseMessage::Depleted {
+                    position: (tx as u16, ty as u16),
+                });
+                tracing::info!("{} épuisé", resource.kind().name);
             }
         }
         _ => {
@@ -198,139 +359,276 @@ pub fn collect_resources(
     }
 }
 
-pub fn get_discovered_map(robot: &mut Robot, discovered: &HashMap<(u16, u16), Tile>) {
-    robot.map_discovered = discovered.clone();
+/// Coût de traversée d'une case d'après les connaissances du robot, `None` si elle est
+/// inconnue ou infranchissable pour lui.
+pub fn known_cost(robot_type: &RobotType, known: Option<&Cell>) -> Option<u16> {
+    known.and_then(|cell| cell.move_cost(robot_type))
 }
 
-pub fn go_to_nearest_point(robot: &mut Robot, target: RobotPosition) {
-    let result = astar(
-        &robot.position,
-        |p: &RobotPosition| {
-            p.successors()
-                .into_iter()
-                .filter(|(next, _)| {
-                    *next == target
-                        || matches!(
-                            robot.map_discovered.get(&(next.0, next.1)),
-                            Some(Tile::Explored)
-                                | Some(Tile::SourceFound(_))
-                                | Some(Tile::CristalFound(_))
-                                | Some(Tile::Floor)
-                                | Some(Tile::Base)
-                        )
-                })
-                .collect::<Vec<_>>()
-        },
-        |p| p.distance(&target),
-        |p| *p == target,
-    );
+/// Un robot sur un terrain lent attend avant de repartir. Retourne `true` s'il attend ce tick.
+pub fn wait_on_terrain(robot: &mut Robot) -> bool {
+    if robot.move_cooldown > 0 {
+        robot.move_cooldown -= 1;
+        true
+    } else {
+        false
+    }
+}
 
-    if let Some((path, _cost)) = result {
-        if path.len() > 1 {
-            robot.position = path[1];
-        }
+/// Après un déplacement, le coût de la case d'arrivée fixe le temps d'attente.
+pub fn apply_terrain_cost(robot: &mut Robot, previous: RobotPosition, map: &[Vec<Cell>]) {
+    if robot.position != previous {
+        let cell = &map[robot.position.1 as usize][robot.position.0 as usize];
+        let cost = cell.move_cost(&robot.robot_type).unwrap_or(1);
+        robot.move_cooldown = move_wait(robot, cost);
+    }
+}
+
+/// Ticks d'attente après être entré sur une case de coût `cost`.
+pub fn move_wait(robot: &Robot, cost: u16) -> u16 {
+    let wait = (cost - 1).saturating_sub(robot.upgrades.speed_bonus());
+    // Batterie vide : le robot se traîne jusqu'à la base
+    if robot.energy == 0 {
+        wait.max(EMPTY_BATTERY_WAIT)
     } else {
+        wait
+    }
+}
+
+/// Attente après chaque pas d'un robot à la batterie vide.
+const EMPTY_BATTERY_WAIT: u16 = 3;
+/// Énergie gardée en réserve en plus de la distance à la base.
+const ENERGY_MARGIN: u32 = 10;
+/// Énergie rechargée par tick passé sur la base.
+const RECHARGE_RATE: u32 = 30;
+
+/// Un aller-retour de `steps` pas depuis la base, réserve comprise, tient dans une batterie
+/// de `capacity`.
+pub fn round_trip_fits(capacity: u32, steps: u32) -> bool {
+    2 * steps + ENERGY_MARGIN <= capacity
+}
+
+/// Après le tick : dépense un point par pas, recharge sur la base et décide du retour
+/// pour recharger quand la réserve ne couvre plus que le trajet de retour.
+pub fn update_energy(robot: &mut Robot, previous: RobotPosition, home_field: &FlowField) {
+    let capacity = robot.upgrades.energy_capacity();
+    if at_base(robot) {
+        robot.energy = (robot.energy + RECHARGE_RATE).min(capacity);
+        if robot.state == RobotState::Charging && robot.energy == capacity {
+            lifecycle::enter(robot, RobotState::Idle);
+        }
+        return;
+    }
+    if robot.position != previous && !robot.towed {
+        robot.energy = robot.energy.saturating_sub(1);
+    }
+    // Pas du vrai trajet de retour, chacun coûtant un point ; à vol d'oiseau seulement si la
+    // case n'est pas encore reliée à la base dans le champ
+    let distance = home_field
+        .steps(robot.robot_type, robot.position)
+        .unwrap_or_else(|| robot.position.distance(&robot.home) as u32);
+    // Un robot immobilisé attend d'abord d'être dégagé ou réparé
+    if !matches!(robot.state, RobotState::Charging | RobotState::Stuck)
+        && robot.energy <= distance + ENERGY_MARGIN
+    {
+        lifecycle::enter(robot, RobotState::Charging);
+        tracing::info!(
+            "Robot {}: batterie faible ({}), retour à la base",
+            robot.id,
+            robot.energy
+        );
+    }
+}
+
+/// Un robot en `Charging` rentre à la base et y attend d'avoir fait le plein, sur la case
+/// de la base où il se trouve.
+pub fn return_to_charge(
+    robot: &mut Robot,
+    known: &HashMap<(u16, u16), Cell>,
+    home_field: &FlowField,
+) {
+    if !at_base(robot) {
+        go_home(robot, known, home_field);
+    }
+}
+
+/// Ce que le robot sait d'une case : les connaissances de l'essaim, partagées par référence,
+/// sinon ses propres observations pas encore mises en commun.
+pub fn known_cell<'a>(
+    robot: &'a Robot,
+    known: &'a HashMap<(u16, u16), Cell>,
+    position: RobotPosition,
+) -> Option<&'a Cell> {
+    let key = (position.0, position.1);
+    known.get(&key).or_else(|| robot.map_discovered.get(&key))
+}
+
+/// Avance d'un pas vers `target` par les cases connues. Retourne `false` si aucun chemin connu n'existe.
+///
+/// Le chemin est gardé d'un tick à l'autre et n'est recalculé que si la cible change, si le
+/// robot a été déplacé, ou si ce qu'il sait d'une case restante a changé. Un échec est gardé
+/// de même jusqu'à ce que le robot bouge ou que `revision` (`FlowField::revision`) change.
+pub fn go_to_nearest_point(
+    robot: &mut Robot,
+    target: RobotPosition,
+    known: &HashMap<(u16, u16), Cell>,
+    revision: u64,
+) -> bool {
+    if !robot
+        .path
+        .as_ref()
+        .is_some_and(|path| path.is_valid(robot, target, known))
+    {
+        if robot
+            .failed_search
+            .as_ref()
+            .is_some_and(|failed| failed.covers(robot.position, revision, &[target]))
+        {
+            robot.path = None;
+            return false;
+        }
+        robot.path = planning::plan(robot, target, known);
+    }
+    let Some(path) = &mut robot.path else {
         tracing::warn!("Aucun chemin trouvé vers {:?}", target);
+        robot.failed_search = Some(FailedSearch::new(robot.position, revision, vec![target]));
+        return false;
+    };
+    if let Some(next) = path.advance() {
+        robot.position = next;
+    }
+    true
+}
+
+/// Un pas de collecteur vers `target` : par un itinéraire coopératif quand une table de
+/// réservation est donnée et qu'il en trouve un, sinon par son propre chemin.
+fn travel(
+    robot: &mut Robot,
+    target: RobotPosition,
+    known: &HashMap<(u16, u16), Cell>,
+    home_field: &FlowField,
+    traffic: Option<&mut Reservations>,
+) {
+    if let Some(reservations) = traffic
+        && cooperative::step(robot, target, known, reservations)
+    {
+        return;
+    }
+    if target == robot.home {
+        go_home(robot, known, home_field);
+    } else {
+        go_to_nearest_point(robot, target, known, home_field.revision());
     }
 }
 
+/// Avance d'un pas vers la base en suivant le champ de distance partagé. Un robot hors du
+/// champ (case inconnue de l'essaim ou sans chemin connu) planifie son propre chemin.
+/// Retourne `false` si aucun chemin connu n'existe.
+pub fn go_home(
+    robot: &mut Robot,
+    known: &HashMap<(u16, u16), Cell>,
+    home_field: &FlowField,
+) -> bool {
+    if robot.position == robot.home {
+        return true;
+    }
+    match home_field.next_step(robot.robot_type, robot.position) {
+        Some(next) if robot.home == home_field.origin() => {
+            robot.position = next;
+            true
+        }
+        _ => go_to_nearest_point(robot, robot.home, known, home_field.revision()),
+    }
+}
+
+/// Un tick d'éclaireur. Retourne les cases qu'il a observées ou signalées, à mettre en
+/// commun avec les connaissances de l'essaim.
 #[allow(clippy::too_many_arguments)]
 pub fn move_robot(
     robot: &mut Robot,
-    map: &mut [Vec<Tile>],
+    map: &[Vec<Cell>],
     width: u16,
     height: u16,
     other_eclaireurs_positions: &HashSet<(u16, u16)>,
     last_visited: &HashMap<(u16, u16), usize>,
     current_robot_id: usize,
     pending_resources: &mut HashSet<(u16, u16)>,
-) {
+    known: &HashMap<(u16, u16), Cell>,
+    home_field: &FlowField,
+) -> Vec<((u16, u16), Cell)> {
     let current_position = robot.position;
-    let center_map = RobotPosition(width / 2, height / 2);
-
-    if matches!(
-        map[current_position.1 as usize][current_position.0 as usize],
-        Tile::Floor | Tile::Base
-    ) {
-        map[current_position.1 as usize][current_position.0 as usize] = Tile::Explored;
-        robot
-            .map_discovered
-            .insert((current_position.0, current_position.1), Tile::Explored);
-    }
-
-    let around_robot = robot_vision(robot, map, width, height);
-
-    if around_robot.iter().any(
-        |(&pos, tile)| {
-            matches!(tile, Tile::Cristal(_) | Tile::Source(_)) && !pending_resources.contains(&pos)
-        }, // ← Vérifier aussi pending_resources !
-    ) && !robot.found_resources
-    {
-        robot.found_resources = true;
-    }
-
-    for (&(x, y), tile) in &around_robot {
-        match tile {
-            Tile::Source(qty) if !pending_resources.contains(&(x, y)) => {
-                pending_resources.insert((x, y));
-
-                let target_resource = Tile::SourceFound(*qty);
-                robot.carried_resource = Some(target_resource);
-                robot.target_resource = Some(RobotPosition(x, y));
-            }
-            Tile::Cristal(qty) if !pending_resources.contains(&(x, y)) => {
-                pending_resources.insert((x, y));
-
-                let target_resource = Tile::CristalFound(*qty);
-                robot.carried_resource = Some(target_resource);
-                robot.target_resource = Some(RobotPosition(x, y));
-            }
-            _ => {}
+    let center_map = robot.home;
+    let here = (current_position.0, current_position.1);
+
+    // La case courante devient connue ; un gisement n'y figure que s'il a déjà été signalé
+    let known_deposit = known_cell(robot, known, current_position).and_then(|cell| cell.deposit);
+    let observed = Cell {
+        deposit: known_deposit,
+        ..map[here.1 as usize][here.0 as usize]
+    };
+    robot.map_discovered.insert(here, observed);
+    let mut observations = vec![(here, observed)];
+
+    // Le robot voit aussi la case sous lui
+    let mut around_robot = robot_vision(robot, map, width, height);
+    around_robot.insert(here, map[here.1 as usize][here.0 as usize]);
+
+    // Un éclaireur ne rapporte qu'une ressource à la fois : les autres restent
+    // des cibles d'exploration et seront signalées au prochain passage
+    for (&(x, y), cell) in &around_robot {
+        if robot.found_resource.is_some() {
+            break;
+        }
+        let Some(deposit) = cell.deposit_left() else {
+            continue;
+        };
+        let reported = known_cell(robot, known, RobotPosition(x, y))
+            .is_some_and(|cell| cell.deposit.is_some());
+        if !reported && !pending_resources.contains(&(x, y)) {
+            pending_resources.insert((x, y));
+            robot.target_resource = Some(RobotPosition(x, y));
+            robot.found_resource = Some(deposit.resource);
         }
     }
 
-    if robot.found_resources && current_position == center_map {
-        robot.found_resources = false;
-        let ressource_found = robot.target_resource;
-        if let Some(ressource_found) = ressource_found {
-            map[ressource_found.1 as usize][ressource_found.0 as usize] =
-                robot.carried_resource.clone().unwrap();
-            robot.map_discovered.insert(
-                (ressource_found.0, ressource_found.1),
-                robot.carried_resource.clone().unwrap(),
-            );
-            pending_resources.remove(&(ressource_found.0, ressource_found.1));
+    if robot.found_resource.is_some() && current_position == center_map {
+        robot.found_resource = None;
+        if let Some(found) = robot.target_resource {
+            // Le rapport inscrit la case telle qu'elle est, gisement compris
+            let reported = map[found.1 as usize][found.0 as usize];
+            robot.map_discovered.insert((found.0, found.1), reported);
+            observations.push(((found.0, found.1), reported));
+            pending_resources.remove(&(found.0, found.1));
         }
+        robot.target_resource = None;
     }
 
-    if robot.found_resources && current_position != center_map {
-        go_to_nearest_point(robot, center_map);
-        return;
+    // Sans chemin connu vers la base (départ hors de la base), on continue d'explorer
+    if robot.found_resource.is_some()
+        && current_position != center_map
+        && go_home(robot, known, home_field)
+    {
+        lifecycle::enter(robot, RobotState::ReturningWithReport);
+        return observations;
     }
 
     let path = bfs(
         &current_position,
         |pos| {
-            pos.successors()
-                .into_iter()
-                .filter(|(p, _)| {
+            robot
+                .movement
+                .steps(*pos, |p| {
                     // BLOQUER : Ne pas aller sur une case occupée par un autre éclaireur
                     if other_eclaireurs_positions.contains(&(p.0, p.1)) {
-                        return false;
+                        return None;
                     }
 
-                    (p.0 < width) && (p.1 < height) && {
-                        let tile = &map[p.1 as usize][p.0 as usize];
-                        matches!(
-                            tile,
-                            Tile::Floor
-                                | Tile::Explored
-                                | Tile::Base
-                                | Tile::SourceFound(_)
-                                | Tile::CristalFound(_)
-                        )
-                    }
+                    ((p.0 < width)
+                        && (p.1 < height)
+                        && map[p.1 as usize][p.0 as usize].is_passable(&robot.robot_type))
+                    .then_some(1)
                 })
+                .into_iter()
                 .map(|(p, _)| p)
                 .collect::<Vec<_>>()
         },
@@ -341,7 +639,7 @@ pub fn move_robot(
                 .is_some_and(|&visitor_id| visitor_id != current_robot_id);
 
             let is_preferred_direction = if let Some((dx, dy)) = robot.direction {
-                if robot.map_discovered.len() < 20 {
+                if known.len() < 20 {
                     let diff_x = p.0 as i16 - current_position.0 as i16;
                     let diff_y = p.1 as i16 - current_position.1 as i16;
                     (diff_x * dx + diff_y * dy) > 0
@@ -352,44 +650,56 @@ pub fn move_robot(
                 true
             };
 
-            // La case doit être non explorée ET pas visitée par un autre robot
+            // Gisement vu mais pas encore signalé ni réservé par un autre éclaireur
+            let unreported_deposit = map[p.1 as usize][p.0 as usize].deposit_left().is_some()
+                && !pending_resources.contains(&(p.0, p.1))
+                && known_cell(robot, known, *p).is_none_or(|cell| cell.deposit.is_none());
+
+            // La case doit être inconnue (ou porter un gisement à signaler) ET pas visitée par un autre robot
             is_preferred_direction
                 && !visited_by_other
-                && !matches!(
-                    robot.map_discovered.get(&(p.0, p.1)),
-                    Some(Tile::Explored)
-                        | Some(Tile::SourceFound(_))
-                        | Some(Tile::CristalFound(_))
-                        | Some(Tile::Base)
-                )
+                && (known_cell(robot, known, *p).is_none() || unreported_deposit)
         },
     );
 
     if let Some(path) = path {
+        lifecycle::enter(robot, RobotState::Exploring);
         if path.len() > 1 {
             let next_pos = path[1];
             robot.position = next_pos;
         }
     } else {
+        // Plus rien à explorer d'ici : l'éclaireur rentre plutôt que de tourner sur place
         tracing::info!("🔄 Aucune case non explorée accessible");
+        lifecycle::enter(robot, RobotState::Idle);
+        go_home(robot, known, home_field);
     }
+    observations
 }
+
+/// Gisement minable non réservé le plus proche en temps de trajet, terrain compris.
+///
+/// Depuis la base, les distances sont lues dans le champ partagé ; ailleurs un Dijkstra
+/// est lancé depuis la position du robot, sauf s'il a déjà échoué d'ici vers les mêmes
+/// gisements sans que les connaissances aient changé.
 pub fn find_nearest_resource(
-    robot: &Robot,
-    discovered: &HashMap<(u16, u16), Tile>,
+    robot: &mut Robot,
+    discovered: &HashMap<(u16, u16), Cell>,
     reserved: &HashSet<(u16, u16)>,
+    home_field: &FlowField,
 ) -> Option<RobotPosition> {
+    let capacity = robot.upgrades.energy_capacity();
     let resource_positions: Vec<RobotPosition> = discovered
         .iter()
-        .filter(|(pos, tile)| {
-            if reserved.contains(pos) {
-                return false;
-            }
-            match tile {
-                Tile::Source(qty) | Tile::SourceFound(qty) => *qty > 0,
-                Tile::Cristal(qty) | Tile::CristalFound(qty) => *qty > 0,
-                _ => false,
-            }
+        .filter(|(pos, cell)| {
+            !reserved.contains(pos)
+                && cell
+                    .deposit_left()
+                    .is_some_and(|deposit| can_mine(robot, deposit.resource))
+                // Hors de portée de la batterie, le robot ferait demi-tour à chaque sortie
+                && home_field
+                    .steps(robot.robot_type, RobotPosition(pos.0, pos.1))
+                    .is_none_or(|steps| round_trip_fits(capacity, steps))
         })
         .map(|(&pos, _)| RobotPosition(pos.0, pos.1))
         .collect();
@@ -399,26 +709,42 @@ pub fn find_nearest_resource(
         return None;
     }
 
-    let result = bfs(
+    // Depuis la base, le champ donne déjà les distances ; d'un poste d'accostage, il compte
+    // au plus un pas de trop (le détour par le centre)
+    if at_base(robot) && robot.home == home_field.origin() {
+        return resource_positions
+            .into_iter()
+            .filter_map(|pos| Some((home_field.distance(robot.robot_type, pos)?, pos)))
+            .min_by_key(|&(distance, pos)| (distance, pos.0, pos.1))
+            .map(|(_, pos)| pos);
+    }
+
+    let revision = home_field.revision();
+    if robot
+        .failed_search
+        .as_ref()
+        .is_some_and(|failed| failed.covers(robot.position, revision, &resource_positions))
+    {
+        return None;
+    }
+    let result = dijkstra(
         &robot.position,
         |pos| {
-            pos.successors()
-                .into_iter()
-                .filter(|(next_pos, _)| {
-                    matches!(
-                        discovered.get(&(next_pos.0, next_pos.1)),
-                        Some(Tile::Explored)
-                            | Some(Tile::SourceFound(_))
-                            | Some(Tile::CristalFound(_))
-                            | Some(Tile::Floor)
-                            | Some(Tile::Base)
-                    )
-                })
-                .map(|(pos, _)| pos)
-                .collect::<Vec<_>>()
+            robot.movement.steps(*pos, |next| {
+                known_cost(&robot.robot_type, discovered.get(&(next.0, next.1)))
+            })
         },
         |pos| resource_positions.contains(pos),
     );
 
-    result.and_then(|path| path.into_iter().last())
+    let nearest = result.and_then(|(path, _cost)| path.into_iter().last());
+    if nearest.is_none() {
+        tracing::info!("🔄 Aucune ressource accessible depuis {:?}", robot.position);
+        robot.failed_search = Some(FailedSearch::new(
+            robot.position,
+            revision,
+            resource_positions,
+        ));
+    }
+    nearest
 }
@@ -0,0 +1,210 @@
+use std::collections::{HashMap, HashSet};
+
+use pathfinding::prelude::dijkstra;
+use tokio::sync::mpsc::Sender;
+
+use crate::base::BaseMessage;
+use crate::docking::{DockState, Docking};
+use crate::flow_field::FlowField;
+use crate::lifecycle::{self, RobotState};
+use crate::map::{Cell, Terrain};
+use crate::resources::Resource;
+use crate::robot::{self, Robot, RobotPosition, RobotType};
+
+/// Au-delà de cette distance (Manhattan) de la base, un collecteur laisse sa cargaison
+/// sur place pour les transporteurs.
+pub const DROP_DISTANCE: u16 = 20;
+/// Ticks de travail pour percer un mur ou combler de l'eau.
+pub const DIG_TIME: u16 = 4;
+/// Coût d'une case à creuser dans le calcul du passage d'un bâtisseur.
+const DIG_COST: u16 = 5;
+/// Ticks sans bouger avec une tâche en cours avant qu'un robot soit considéré immobilisé.
+pub const STRANDED_AFTER: u32 = 40;
+
+/// Cargaisons laissées sur le terrain : unités par case et par ressource.
+pub type Drops = HashMap<((u16, u16), Resource), u32>;
+
+/// Un collecteur chargé, loin de la base, dépose sa cargaison sur sa case au lieu de
+/// rentrer. Retourne `true` s'il l'a déposée.
+pub fn drop_cargo(robot: &mut Robot, drops: &mut Drops) -> bool {
+    let Some(resource) = robot.carried_resource else {
+        return false;
+    };
+    let distance =
+        robot.position.0.abs_diff(robot.home.0) + robot.position.1.abs_diff(robot.home.1);
+    if robot.collected_resources == 0 || distance <= DROP_DISTANCE {
+        return false;
+    }
+    let here = (robot.position.0, robot.position.1);
+    *drops.entry((here, resource)).or_insert(0) += robot.collected_resources;
+    tracing::info!(
+        "{} {} laissés en {:?} pour les transporteurs",
+        robot.collected_resources,
+        resource.kind().name,
+        here
+    );
+    robot.collected_resources = 0;
+    robot.carried_resource = None;
+    true
+}
+
+/// Transporteur : va chercher la cargaison laissée la plus proche et la livre à la base.
+pub fn haul(
+    robot: &mut Robot,
+    drops: &mut Drops,
+    reserved: &mut HashSet<(u16, u16)>,
+    known: &HashMap<(u16, u16), Cell>,
+    home_field: &FlowField,
+    docking: &mut Docking,
+    tx_base: &Sender<BaseMessage>,
+) {
+    if robot::deliver_cargo(robot, docking, known, home_field, None, tx_base) {
+        return;
+    }
+
+    let target = match robot.target_resource {
+        Some(target) => target,
+        None => {
+            let nearest = drops
+                .keys()
+                .map(|&(pos, _)| pos)
+                .filter(|pos| !reserved.contains(pos))
+                .min_by_key(|&(x, y)| {
+                    (
+                        x.abs_diff(robot.position.0) + y.abs_diff(robot.position.1),
+                        (x, y),
+                    )
+                });
+            let Some((x, y)) = nearest else {
+                lifecycle::enter(robot, RobotState::Idle);
+                robot::go_home(robot, known, home_field);
+                return;
+            };
+            reserved.insert((x, y));
+            robot.target_resource = Some(RobotPosition(x, y));
+            lifecycle::enter(robot, RobotState::GoingToDeposit);
+            RobotPosition(x, y)
+        }
+    };
+
+    if robot.position != target {
+        robot::go_to_nearest_point(robot, target, known, home_field.revision());
+        return;
+    }
+
+    // Sur place : charge une ressource de la case, la suivante fera l'objet d'un autre trajet
+    robot.target_resource = None;
+    let here = (target.0, target.1);
+    let Some(resource) = Resource::all().find(|&r| drops.contains_key(&(here, r))) else {
+        return;
+    };
+    let amount = drops.remove(&(here, resource)).unwrap_or(0);
+    robot.collected_resources = amount;
+    robot.carried_resource = Some(resource);
+    tracing::info!(
+        "Transporteur: {} {} chargés en {:?}",
+        amount,
+        resource.kind().name,
+        here
+    );
+}
+
+/// Bâtisseur : se dirige vers le gisement inaccessible le plus proche en perçant les murs
+/// et en comblant l'eau sur son passage. Retourne la case transformée ce tick.
+///
+/// Le passage est calculé sur le monde réel : les gisements visés sont ceux que
+/// `GameState::detect_unreachable_deposits` a recensés.
+pub fn build(
+    robot: &mut Robot,
+    map: &mut [Vec<Cell>],
+    unreachable: &[(u16, u16)],
+    reserved: &mut HashSet<(u16, u16)>,
+    known: &HashMap<(u16, u16), Cell>,
+    home_field: &FlowField,
+) -> Option<(u16, u16)> {
+    if robot
+        .target_resource
+        .is_some_and(|t| !unreachable.contains(&(t.0, t.1)))
+    {
+        // Le gisement est désormais relié à la base : travail terminé
+        robot.target_resource = None;
+    }
+    if robot.target_resource.is_none() {
+        let nearest = unreachable
+            .iter()
+            .filter(|pos| !reserved.contains(pos))
+            .min_by_key(|&&(x, y)| x.abs_diff(robot.position.0) + y.abs_diff(robot.position.1));
+        let Some(&(x, y)) = nearest else {
+            lifecycle::enter(robot, RobotState::Idle);
+            robot::go_home(robot, known, home_field);
+            return None;
+        };
+        reserved.insert((x, y));
+        robot.target_resource = Some(RobotPosition(x, y));
+        lifecycle::enter(robot, RobotState::GoingToDeposit);
+        tracing::info!("Bâtisseur: passage vers ({}, {})", x, y);
+    }
+    let target = robot.target_resource?;
+
+    let (height, width) = (map.len() as u16, map[0].len() as u16);
+    let (path, _) = dijkstra(
+        &robot.position,
+        |p: &RobotPosition| {
+            let mut next = Vec::new();
+            for (dx, dy) in [(1i16, 0i16), (-1, 0), (0, 1), (0, -1)] {
+                let (Some(nx), Some(ny)) = (p.0.checked_add_signed(dx), p.1.checked_add_signed(dy))
+                else {
+                    continue;
+                };
+                if nx >= width || ny >= height {
+                    continue;
+                }
+                let cost = map[ny as usize][nx as usize]
+                    .move_cost(&RobotType::Batisseur)
+                    .unwrap_or(DIG_COST);
+                next.push((RobotPosition(nx, ny), cost));
+            }
+            next
+        },
+        |p| *p == target,
+    )?;
+    let &next = path.get(1)?;
+
+    let cell = &mut map[next.1 as usize][next.0 as usize];
+    if cell.is_passable(&RobotType::Batisseur) {
+        robot.position = next;
+        return None;
+    }
+    cell.terrain = Terrain::Floor;
+    robot.move_cooldown = DIG_TIME - 1;
+    tracing::info!("Bâtisseur: passage ouvert en ({}, {})", next.0, next.1);
+    Some((next.0, next.1))
+}
+
+/// Robot qui n'avance plus alors qu'il a une tâche (cible, cargaison ou découverte à rapporter)
+/// ou qu'il est en panne.
+pub fn is_stranded(robot: &Robot) -> bool {
+    !robot.towed && robot.idle_ticks >= STRANDED_AFTER
+}
+
+/// Met à jour le compteur d'immobilité après le tick du robot.
+pub fn track_idle(robot: &mut Robot, previous: RobotPosition) {
+    // Miner sur place n'est pas de l'immobilité
+    let busy = robot.target_resource.is_some_and(|t| t != robot.position)
+        || robot.collected_resources > 0
+        || robot.found_resource.is_some()
+        || (robot.state == RobotState::Charging && !robot::at_base(robot))
+        || robot.broken
+        || robot.recovery.is_some();
+    // Une attente prévue par l'itinéraire coopératif, ou son tour à la base, non plus
+    let following = robot.route.as_ref().is_some_and(|route| route.is_active());
+    let docking = matches!(
+        robot.dock,
+        Some(DockState::Queued | DockState::Unloading(..) | DockState::Confirming(..))
+    );
+    if robot.position != previous || !busy || robot.towed || following || docking {
+        robot.idle_ticks = 0;
+    } else {
+        robot.idle_ticks += 1;
+    }
+}
@@ -0,0 +1,82 @@
+use std::collections::{HashMap, VecDeque};
+
+use crate::lifecycle::RobotState;
+use crate::map::{Cell, Terrain};
+use crate::robot::Robot;
+
+/// Nombre de ticks conservés pour les graphiques du tableau de bord.
+pub const HISTORY_LEN: usize = 256;
+
+#[derive(Default)]
+pub struct StatsHistory {
+    /// Totaux livrés par type de ressource, indexés comme `resources::REGISTRY`.
+    pub totals: Vec<VecDeque<u64>>,
+    /// Couverture d'exploration en pour mille.
+    pub coverage: VecDeque<u64>,
+    /// Unités déposées à la base pendant le tick.
+    pub collection_rate: VecDeque<u64>,
+    /// Nombre de robots dans chaque état du cycle de vie.
+    pub states: Vec<(&'static str, u64)>,
+    /// Changements d'état cumulés depuis le début de la partie.
+    pub transitions: u64,
+    /// Ticks passés dans chaque état, cumulés sur tous les robots, dans l'ordre de `RobotState::ALL`.
+    pub state_ticks: [u64; RobotState::ALL.len()],
+}
+
+fn push_sample(history: &mut VecDeque<u64>, value: u64) {
+    if history.len() == HISTORY_LEN {
+        history.pop_front();
+    }
+    history.push_back(value);
+}
+
+impl StatsHistory {
+    pub fn record(
+        &mut self,
+        totals: &[u32],
+        map: &[Vec<Cell>],
+        map_discovered: &HashMap<(u16, u16), Cell>,
+        robots: &[Robot],
+    ) {
+        self.totals.resize_with(totals.len(), VecDeque::new);
+        let previous_total: u64 = self
+            .totals
+            .iter()
+            .map(|history| history.back().copied().unwrap_or(0))
+            .sum();
+        let total: u64 = totals.iter().map(|&amount| amount as u64).sum();
+        for (history, &amount) in self.totals.iter_mut().zip(totals) {
+            push_sample(history, amount as u64);
+        }
+        push_sample(
+            &mut self.collection_rate,
+            total.saturating_sub(previous_total),
+        );
+
+        let passable = map
+            .iter()
+            .flatten()
+            .filter(|cell| cell.terrain != Terrain::Wall)
+            .count() as u64;
+        let known = map_discovered.len() as u64;
+        push_sample(
+            &mut self.coverage,
+            (known * 1000).checked_div(passable).unwrap_or(0),
+        );
+
+        self.states = RobotState::ALL
+            .iter()
+            .map(|state| {
+                let count = robots.iter().filter(|r| r.state == *state).count() as u64;
+                (state.label(), count)
+            })
+            .collect();
+        for (ticks, (_, count)) in self.state_ticks.iter_mut().zip(&self.states) {
+            *ticks += count;
+        }
+    }
+
+    pub fn coverage_percent(&self) -> f64 {
+        self.coverage.back().copied().unwrap_or(0) as f64 / 10.0
+    }
+}
@@ -0,0 +1,498 @@
+use crate::game_state::GameState;
+use crate::resources::{REGISTRY, Resource};
+use crate::{map, robot};
+use ratatui::{
+    Frame,
+    crossterm::event::{MouseButton, MouseEvent, MouseEventKind},
+    prelude::*,
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{BarChart, Block, Borders, Gauge, Paragraph, Sparkline},
+};
+use std::collections::VecDeque;
+
+/// Largeur du panneau de statistiques.
+const DASHBOARD_WIDTH: u16 = 36;
+
+/// Vue affichée par le rendu de la carte.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum ViewMode {
+    /// Monde réel avec les découvertes de l'essaim (gisements signalés, cases explorées).
+    #[default]
+    Simulation,
+    /// Contenu réel du monde, sans état de découverte.
+    Truth,
+    /// Connaissances agrégées de l'essaim (`map_discovered`).
+    Swarm,
+    /// Observations propres d'un seul robot.
+    Robot,
+    /// Carte de chaleur des derniers passages.
+    Heatmap,
+}
+
+impl ViewMode {
+    pub fn next(self) -> Self {
+        match self {
+            ViewMode::Simulation => ViewMode::Truth,
+            ViewMode::Truth => ViewMode::Swarm,
+            ViewMode::Swarm => ViewMode::Robot,
+            ViewMode::Robot => ViewMode::Heatmap,
+            ViewMode::Heatmap => ViewMode::Simulation,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ViewMode::Simulation => "Simulation",
+            ViewMode::Truth => "Vérité terrain",
+            ViewMode::Swarm => "Connaissance essaim",
+            ViewMode::Robot => "Connaissance robot",
+            ViewMode::Heatmap => "Chaleur",
+        }
+    }
+}
+
+/// Outil d'édition appliqué au clic quand la simulation est en pause.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum EditTool {
+    #[default]
+    Wall,
+    /// Pose un gisement du type donné ; touche : lettre du registre en minuscule.
+    Deposit(Resource),
+    MoveRobot,
+}
+
+impl EditTool {
+    pub fn label(self) -> &'static str {
+        match self {
+            EditTool::Wall => "Mur",
+            EditTool::Deposit(resource) => resource.kind().name,
+            EditTool::MoveRobot => "Déplacer robot",
+        }
+    }
+}
+
+/// Quantité des gisements posés à la souris.
+const EDIT_DEPOSIT_QTY: u32 = 8;
+
+#[derive(Default)]
+pub struct UiState {
+    pub view: ViewMode,
+    pub selected_robot: usize,
+    pub show_dashboard: bool,
+    /// Coin haut-gauche de la portion du monde affichée.
+    pub viewport: (u16, u16),
+    /// Zone de l'écran occupée par la carte au dernier rendu.
+    pub map_area: Rect,
+    pub paused: bool,
+    pub tool: EditTool,
+    /// Robot saisi avec l'outil de déplacement, en attente d'une case de destination.
+    pub grabbed_robot: Option<usize>,
+}
+
+impl UiState {
+    pub fn next_view(&mut self) {
+        self.view = self.view.next();
+        tracing::info!("Vue: {}", self.view.label());
+    }
+
+    pub fn toggle_dashboard(&mut self) {
+        self.show_dashboard = !self.show_dashboard;
+    }
+
+    pub fn scroll(&mut self, dx: i16, dy: i16) {
+        self.viewport.0 = self.viewport.0.saturating_add_signed(dx);
+        self.viewport.1 = self.viewport.1.saturating_add_signed(dy);
+    }
+
+    /// Garde la fenêtre dans les limites du monde pour la taille d'écran courante.
+    fn clamp_viewport(&mut self, world_width: u16, world_height: u16) {
+        self.viewport.0 = self
+            .viewport
+            .0
+            .min(world_width.saturating_sub(self.map_area.width));
+        self.viewport.1 = self
+            .viewport
+            .1
+            .min(world_height.saturating_sub(self.map_area.height));
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+        self.grabbed_robot = None;
+        tracing::info!(
+            "Simulation {}",
+            if self.paused { "en pause" } else { "reprise" }
+        );
+    }
+
+    pub fn select_tool(&mut self, tool: EditTool) {
+        self.tool = tool;
+        self.grabbed_robot = None;
+    }
+
+    /// Case du monde sous une position de l'écran.
+    fn screen_to_world(&self, column: u16, row: u16) -> Option<(u16, u16)> {
+        let area = self.map_area;
+        if column < area.x || row < area.y || column >= area.right() || row >= area.bottom() {
+            return None;
+        }
+        Some((
+            column - area.x + self.viewport.0,
+            row - area.y + self.viewport.1,
+        ))
+    }
+
+    /// Édite le monde à la souris : clic gauche pour appliquer l'outil, clic droit pour remettre du sol.
+    pub fn handle_mouse(&mut self, game_state: &mut GameState, mouse: MouseEvent) {
+        if !self.paused {
+            return;
+        }
+        let MouseEventKind::Down(button) = mouse.kind else {
+            return;
+        };
+        let Some((x, y)) = self.screen_to_world(mouse.column, mouse.row) else {
+            return;
+        };
+        if x >= game_state.width || y >= game_state.height {
+            return;
+        }
+
+        match (button, self.tool) {
+            (MouseButton::Right, _) => {
+                game_state.set_cell(x, y, map::Cell::new(map::Terrain::Floor))
+            }
+            (MouseButton::Left, EditTool::Wall) => {
+                game_state.set_cell(x, y, map::Cell::new(map::Terrain::Wall))
+            }
+            (MouseButton::Left, EditTool::Deposit(resource)) => {
+                game_state.set_cell(x, y, map::Cell::with_deposit(resource, EDIT_DEPOSIT_QTY))
+            }
+            (MouseButton::Left, EditTool::MoveRobot) => match self.grabbed_robot.take() {
+                Some(index) => game_state.relocate_robot(index, x, y),
+                None => {
+                    self.grabbed_robot = game_state
+                        .robots
+                        .iter()
+                        .position(|r| r.position.0 == x && r.position.1 == y);
+                }
+            },
+            _ => {}
+        }
+    }
+
+    pub fn next_robot(&mut self, robot_count: usize) {
+        if robot_count > 0 {
+            self.selected_robot = (self.selected_robot + 1) % robot_count;
+        }
+    }
+}
+
+/// Caractère et couleur d'une case ; `known` distingue les gisements signalés et le sol exploré.
+pub fn tile_glyph(cell: &map::Cell, known: bool) -> (char, Color) {
+    if cell.is_base() {
+        return ('#', Color::LightGreen);
+    }
+    match cell.deposit_left() {
+        Some(deposit) => {
+            let kind = deposit.resource.kind();
+            (
+                kind.glyph,
+                if known { kind.known_color } else { kind.color },
+            )
+        }
+        None if cell.hazard => ('!', Color::Red),
+        None => match cell.terrain {
+            map::Terrain::Wall => ('0', Color::LightCyan),
+            map::Terrain::Floor if known => ('░', Color::Gray),
+            map::Terrain::Floor => (' ', Color::Reset),
+            map::Terrain::Sand => (':', Color::LightYellow),
+            map::Terrain::Water => ('~', Color::Blue),
+            map::Terrain::Rubble => ('%', Color::White),
+        },
+    }
+}
+
+fn robot_glyph(robot: &robot::Robot) -> (char, Color) {
+    let robot_type = robot.robot_type;
+    if robot.broken {
+        return (robot_type.glyph(), Color::DarkGray);
+    }
+    let color = match robot_type {
+        robot::RobotType::Eclaireur => Color::Red,
+        robot::RobotType::Collecteur => Color::Magenta,
+        robot::RobotType::Transporteur => Color::LightBlue,
+        robot::RobotType::Batisseur => Color::LightYellow,
+        robot::RobotType::Reparateur => Color::LightGreen,
+    };
+    (robot_type.glyph(), color)
+}
+
+/// Case inconnue : on montre le vrai contenu, atténué.
+fn dimmed(cell: &map::Cell) -> (char, Color) {
+    let (ch, _) = tile_glyph(cell, false);
+    (ch, Color::DarkGray)
+}
+
+fn heat_color(age: u64) -> Color {
+    match age {
+        0..20 => Color::Red,
+        20..100 => Color::LightRed,
+        100..300 => Color::Yellow,
+        300..1000 => Color::Green,
+        _ => Color::Blue,
+    }
+}
+
+fn cell_glyph(game_state: &GameState, ui: &UiState, x: u16, y: u16) -> (char, Color) {
+    let robot_here = game_state
+        .robots
+        .iter()
+        .enumerate()
+        .find(|(_, r)| r.position.0 == x && r.position.1 == y);
+
+    if let Some((index, robot)) = robot_here {
+        if ui.view == ViewMode::Robot && index == ui.selected_robot {
+            return ('@', Color::White);
+        }
+        if ui.grabbed_robot == Some(index) {
+            return ('@', Color::Yellow);
+        }
+        return robot_glyph(robot);
+    }
+
+    // Cargaison laissée pour les transporteurs
+    if matches!(ui.view, ViewMode::Simulation | ViewMode::Truth)
+        && let Some(resource) =
+            Resource::all().find(|&r| game_state.drops.contains_key(&((x, y), r)))
+    {
+        return ('*', resource.kind().color);
+    }
+
+    let cell = &game_state.map[y as usize][x as usize];
+    match ui.view {
+        ViewMode::Simulation => {
+            // Un gisement n'apparaît comme signalé que si l'essaim le connaît
+            let known = game_state
+                .map_discovered
+                .get(&(x, y))
+                .is_some_and(|k| cell.deposit_left().is_none() || k.deposit.is_some());
+            tile_glyph(cell, known)
+        }
+        ViewMode::Truth => tile_glyph(cell, false),
+        ViewMode::Swarm => match game_state.map_discovered.get(&(x, y)) {
+            Some(known) => tile_glyph(known, true),
+            None => dimmed(cell),
+        },
+        // Ce que le robot croit : les connaissances de l'essaim, complétées des siennes
+        ViewMode::Robot => match game_state.robots.get(ui.selected_robot).and_then(|r| {
+            robot::known_cell(r, &game_state.map_discovered, robot::RobotPosition(x, y))
+        }) {
+            Some(known) => tile_glyph(known, true),
+            None => dimmed(cell),
+        },
+        ViewMode::Heatmap => match game_state.visit_ticks.get(&(x, y)) {
+            Some(&visited) => ('▓', heat_color(game_state.tick - visited)),
+            None => dimmed(cell),
+        },
+    }
+}
+
+pub fn render(f: &mut Frame<'_>, game_state: &GameState, ui: &mut UiState) {
+    let [status_area, body_area] =
+        Layout::vertical([Constraint::Length(1), Constraint::Min(0)]).areas(f.area());
+
+    let mut status = Vec::new();
+    for (resource, total) in Resource::all().zip(&game_state.totals) {
+        let kind = resource.kind();
+        status.push(Span::styled(
+            format!("{}: ", kind.name),
+            Style::default().fg(kind.color),
+        ));
+        status.push(Span::styled(
+            total.to_string(),
+            Style::default().fg(Color::White),
+        ));
+        status.push(Span::raw("   "));
+    }
+    status.extend([
+        Span::styled("Score: ", Style::default().fg(Color::LightYellow)),
+        Span::styled(
+            game_state.score.to_string(),
+            Style::default().fg(Color::White),
+        ),
+        Span::raw("   "),
+        Span::styled("Améliorations: ", Style::default().fg(Color::LightMagenta)),
+        Span::styled(
+            format!(
+                "{} ({} cristaux)",
+                game_state.upgrades.short(),
+                game_state.spent_crystals
+            ),
+            Style::default().fg(Color::White),
+        ),
+        Span::raw("   "),
+        Span::styled("Postes: ", Style::default().fg(Color::LightGreen)),
+        Span::styled(
+            format!(
+                "{}/{} (file {})",
+                game_state.docking.busy_slots(),
+                game_state.docking.slot_count(),
+                game_state.docking.queue_len()
+            ),
+            Style::default().fg(Color::White),
+        ),
+        Span::raw("   "),
+        Span::styled("Vue: ", Style::default().fg(Color::Cyan)),
+        Span::styled(ui.view.label(), Style::default().fg(Color::White)),
+    ]);
+    if ui.view == ViewMode::Robot {
+        status.push(Span::styled(
+            format!(" {}/{}", ui.selected_robot + 1, game_state.robots.len()),
+            Style::default().fg(Color::White),
+        ));
+        // État, batterie et améliorations installées sur le robot suivi
+        if let Some(robot) = game_state.robots.get(ui.selected_robot) {
+            status.push(Span::styled(
+                format!(
+                    " {} batterie {}/{} {}",
+                    robot.state.name(),
+                    robot.energy,
+                    robot.upgrades.energy_capacity(),
+                    robot.upgrades.short()
+                ),
+                Style::default().fg(Color::White),
+            ));
+        }
+    }
+    if ui.paused {
+        status.push(Span::raw("   "));
+        status.push(Span::styled(
+            "PAUSE",
+            Style::default().fg(Color::Black).bg(Color::Yellow),
+        ));
+        status.push(Span::styled(
+            format!(" Outil: {}", ui.tool.label()),
+            Style::default().fg(Color::White),
+        ));
+        if ui.grabbed_robot.is_some() {
+            status.push(Span::styled(
+                " (robot saisi)",
+                Style::default().fg(Color::Yellow),
+            ));
+        }
+    }
+    let score_widget = Paragraph::new(vec![Line::from(status)]);
+    f.render_widget(score_widget, status_area);
+
+    let (map_area, dashboard_area) = if ui.show_dashboard && body_area.width > DASHBOARD_WIDTH {
+        let [map_area, dashboard_area] =
+            Layout::horizontal([Constraint::Min(0), Constraint::Length(DASHBOARD_WIDTH)])
+                .areas(body_area);
+        (map_area, Some(dashboard_area))
+    } else {
+        (body_area, None)
+    };
+    ui.map_area = map_area;
+    ui.clamp_viewport(game_state.width, game_state.height);
+
+    let (offset_x, offset_y) = ui.viewport;
+    let visible_width = map_area.width.min(game_state.width - offset_x);
+    let visible_height = map_area.height.min(game_state.height - offset_y);
+    let map_lines: Vec<Line> = (offset_y..offset_y + visible_height)
+        .map(|y| {
+            let spans: Vec<Span> = (offset_x..offset_x + visible_width)
+                .map(|x| {
+                    let (ch, color) = cell_glyph(game_state, ui, x, y);
+                    Span::styled(ch.to_string(), Style::default().fg(color))
+                })
+                .collect();
+            Line::from(spans)
+        })
+        .collect();
+
+    let map_widget = Paragraph::new(map_lines);
+    f.render_widget(map_widget, map_area);
+
+    if let Some(dashboard_area) = dashboard_area {
+        render_dashboard(f, game_state, dashboard_area);
+    }
+}
+
+/// Dernières valeurs d'un historique qui tiennent dans la largeur donnée.
+fn tail(history: &VecDeque<u64>, width: u16) -> Vec<u64> {
+    history
+        .iter()
+        .skip(history.len().saturating_sub(width as usize))
+        .copied()
+        .collect()
+}
+
+fn render_dashboard(f: &mut Frame<'_>, game_state: &GameState, area: Rect) {
+    let stats = &game_state.stats;
+    // Une courbe par ressource du registre, puis collecte, exploration et robots
+    let [resources_area, rate_area, coverage_area, states_area] = Layout::vertical([
+        Constraint::Length(5 * REGISTRY.len() as u16),
+        Constraint::Length(5),
+        Constraint::Length(3),
+        Constraint::Min(6),
+    ])
+    .areas(area);
+    let resource_areas =
+        Layout::vertical(REGISTRY.iter().map(|_| Constraint::Length(5))).split(resources_area);
+    let inner_width = area.width.saturating_sub(2);
+
+    let sparkline = |title: String, history: &VecDeque<u64>, color: Color| {
+        Sparkline::default()
+            .block(Block::default().borders(Borders::ALL).title(title))
+            .data(tail(history, inner_width))
+            .style(Style::default().fg(color))
+    };
+
+    for ((kind, history), (total, resource_area)) in REGISTRY
+        .iter()
+        .zip(&stats.totals)
+        .zip(game_state.totals.iter().zip(resource_areas.iter()))
+    {
+        f.render_widget(
+            sparkline(format!("{} ({})", kind.name, total), history, kind.color),
+            *resource_area,
+        );
+    }
+    f.render_widget(
+        sparkline(
+            format!(
+                "Collecte/tick ({})",
+                stats.collection_rate.back().copied().unwrap_or(0)
+            ),
+            &stats.collection_rate,
+            Color::Yellow,
+        ),
+        rate_area,
+    );
+
+    let coverage = stats.coverage_percent();
+    f.render_widget(
+        Gauge::default()
+            .block(Block::default().borders(Borders::ALL).title("Exploration"))
+            .gauge_style(Style::default().fg(Color::Cyan))
+            .ratio((coverage / 100.0).clamp(0.0, 1.0))
+            .label(format!("{coverage:.1}%")),
+        coverage_area,
+    );
+
+    f.render_widget(
+        BarChart::default()
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(format!("Robots ({} transitions)", stats.transitions)),
+            )
+            .data(stats.states.as_slice())
+            .bar_width(3)
+            .bar_gap(1)
+            .bar_style(Style::default().fg(Color::LightBlue))
+            .value_style(Style::default().fg(Color::Black).bg(Color::LightBlue)),
+        states_area,
+    );
+}
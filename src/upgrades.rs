@@ -0,0 +1,111 @@
+/// Prix en cristaux du premier niveau d'une amélioration ; chaque niveau suivant coûte
+/// ce prix de plus.
+pub const UPGRADE_COST: u32 = 5;
+/// Niveau maximal de chaque amélioration.
+pub const MAX_LEVEL: u8 = 3;
+/// Énergie d'un robot sans amélioration, et gain par niveau.
+pub const BASE_ENERGY: u32 = 300;
+const ENERGY_PER_LEVEL: u32 = 100;
+
+/// Améliorations achetées par la base pour toute la flotte.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum UpgradeKind {
+    /// Rayon de vision.
+    Vision,
+    /// Unités transportées par trajet.
+    Cargo,
+    /// Attente réduite sur les terrains lents.
+    Speed,
+    /// Capacité de la batterie.
+    Energy,
+}
+
+impl UpgradeKind {
+    /// Ordre d'achat à niveau égal.
+    pub const ALL: [UpgradeKind; 4] = [
+        UpgradeKind::Cargo,
+        UpgradeKind::Vision,
+        UpgradeKind::Energy,
+        UpgradeKind::Speed,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            UpgradeKind::Vision => "Vision",
+            UpgradeKind::Cargo => "Soute",
+            UpgradeKind::Speed => "Vitesse",
+            UpgradeKind::Energy => "Batterie",
+        }
+    }
+}
+
+/// Niveaux d'amélioration, ceux de la flotte à la base ou ceux installés sur un robot.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct Upgrades {
+    pub vision: u8,
+    pub cargo: u8,
+    pub speed: u8,
+    pub energy: u8,
+}
+
+impl Upgrades {
+    pub fn level(&self, kind: UpgradeKind) -> u8 {
+        match kind {
+            UpgradeKind::Vision => self.vision,
+            UpgradeKind::Cargo => self.cargo,
+            UpgradeKind::Speed => self.speed,
+            UpgradeKind::Energy => self.energy,
+        }
+    }
+
+    fn level_mut(&mut self, kind: UpgradeKind) -> &mut u8 {
+        match kind {
+            UpgradeKind::Vision => &mut self.vision,
+            UpgradeKind::Cargo => &mut self.cargo,
+            UpgradeKind::Speed => &mut self.speed,
+            UpgradeKind::Energy => &mut self.energy,
+        }
+    }
+
+    /// Prochaine amélioration à acheter (la moins avancée) et son prix, `None` si tout est au maximum.
+    pub fn next_purchase(&self) -> Option<(UpgradeKind, u32)> {
+        UpgradeKind::ALL
+            .into_iter()
+            .filter(|&kind| self.level(kind) < MAX_LEVEL)
+            .min_by_key(|&kind| self.level(kind))
+            .map(|kind| (kind, UPGRADE_COST * (self.level(kind) as u32 + 1)))
+    }
+
+    pub fn raise(&mut self, kind: UpgradeKind) {
+        let level = self.level_mut(kind);
+        *level = (*level + 1).min(MAX_LEVEL);
+    }
+
+    /// Distance de vue (Manhattan) autour du robot.
+    pub fn vision_radius(&self) -> u16 {
+        1 + self.vision as u16
+    }
+
+    /// Unités qu'un collecteur rapporte avant de rentrer.
+    pub fn cargo_capacity(&self) -> u32 {
+        1 + 2 * self.cargo as u32
+    }
+
+    /// Ticks d'attente retirés après un pas sur terrain lent.
+    pub fn speed_bonus(&self) -> u16 {
+        self.speed as u16
+    }
+
+    pub fn energy_capacity(&self) -> u32 {
+        BASE_ENERGY + ENERGY_PER_LEVEL * self.energy as u32
+    }
+
+    /// Résumé court pour le TUI, ex. `Sou2 Vis1 Bat1 Vit0`.
+    pub fn short(&self) -> String {
+        UpgradeKind::ALL
+            .iter()
+            .map(|&kind| format!("{}{}", &kind.label()[..3], self.level(kind)))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}